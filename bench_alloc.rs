@@ -0,0 +1,99 @@
+/// Counts heap allocations made by parsing, adding, subtracting and comparing `Bigint`s that fit
+/// in 64 bits, and times an add/sub loop over them.
+///
+/// rustc -O bench_alloc.rs && ./bench_alloc [values]
+///
+#[path = "homework_02.rs"]
+#[allow(dead_code)]
+mod homework_02;
+
+use homework_02::{Bigint, Rng, XorShift64};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
+
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        return unsafe { System.alloc(layout) };
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) };
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        return unsafe { System.realloc(ptr, layout, new_size) };
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+/// Runs `f` and returns the number of allocations it made, per value.
+fn per_value<F: FnMut()>(values: usize, mut f: F) -> f64 {
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    f();
+
+    return (ALLOCATIONS.load(Ordering::Relaxed) - before) as f64 / values as f64;
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    // the timed loop below works on pairs of values
+    let count: usize = std::cmp::max(args.get(1).and_then(|arg| arg.parse().ok()).unwrap_or(1000), 2);
+    let mut rng = XorShift64::new(43);
+
+    // random signed values below 2^62, so sums and differences still fit in 64 bits
+    let texts: Vec<String> = (0..count)
+        .map(|_| {
+            let magnitude = rng.next_u64() >> (2 + rng.next_u64() % 60);
+            if rng.next_u64().is_multiple_of(2) { magnitude.to_string() } else { format!("-{}", magnitude) }
+        })
+        .collect();
+
+    let mut numbers: Vec<Bigint> = Vec::with_capacity(count);
+    let parse = per_value(count, || {
+        for text in &texts {
+            numbers.push(text.parse().unwrap());
+        }
+    });
+
+    let mut results: Vec<Bigint> = Vec::with_capacity(count);
+    let add = per_value(count, || {
+        for pair in numbers.windows(2) {
+            results.push(pair[0].clone() + pair[1].clone());
+        }
+    });
+    results.clear();
+    let sub = per_value(count, || {
+        for pair in numbers.windows(2) {
+            results.push(pair[0].clone() - pair[1].clone());
+        }
+    });
+    let mut less = 0;
+    let cmp = per_value(count, || {
+        for pair in numbers.windows(2) {
+            less += (pair[0] < pair[1]) as usize;
+        }
+    });
+
+    println!("allocations per value over {} values:", count);
+    println!("  parse {:.2}   add {:.2}   sub {:.2}   cmp {:.2}", parse, add, sub, cmp);
+
+    let rounds = 100_000;
+    let start = Instant::now();
+    for i in 0..rounds {
+        let (left, right) = (&numbers[i % count], &numbers[(i + 1) % count]);
+        results[i % (count - 1)] = left.clone() + right.clone();
+        results[(i + 1) % (count - 1)] = left.clone() - right.clone();
+    }
+    let elapsed = start.elapsed();
+    println!("{} adds and {} subs: {:.2?}", rounds, rounds, elapsed);
+    println!("{} values are less than the next one", less);
+}
@@ -0,0 +1,72 @@
+/// Compares `Montgomery`/`ModInt` arithmetic with reducing by `%` after every operation, the way
+/// `Bigint::modpow` does.
+///
+/// rustc -O bench_montgomery.rs && ./bench_montgomery [modulus bits] [exponent bits]
+///
+#[path = "homework_02.rs"]
+#[allow(dead_code)]
+mod homework_02;
+
+use homework_02::{Bigint, Montgomery, XorShift64};
+use std::time::{Duration, Instant};
+
+fn time<T, F: FnMut() -> T>(runs: u32, mut f: F) -> (T, Duration) {
+    let start = Instant::now();
+    let mut res = f();
+    for _ in 1..runs {
+        res = f();
+    }
+
+    return (res, start.elapsed() / runs);
+}
+
+fn report(label: &str, naive: Duration, montgomery: Duration) {
+    println!("{:<12} reduce each op {:>10.2?}   Montgomery {:>10.2?}", label, naive, montgomery);
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    let modulus_bits = args.get(1).and_then(|arg| arg.parse().ok()).unwrap_or(521);
+    let exponent_bits = args.get(2).and_then(|arg| arg.parse().ok()).unwrap_or(300);
+    let mut rng = XorShift64::new(34);
+
+    // an odd modulus that isn't a multiple of 5, so the Montgomery path is measured and not the
+    // division it falls back to for the others
+    let mut modulus = Bigint::random_with_bit_length(&mut rng, modulus_bits);
+    modulus.set_bit(0, true);
+    let (two, five): (Bigint, Bigint) = ("2".parse().unwrap(), "5".parse().unwrap());
+    while (modulus.clone() % five.clone()).is_zero() {
+        modulus = modulus + two.clone();
+    }
+    let base = Bigint::random_below(&mut rng, &modulus).unwrap();
+    let exponent = Bigint::random_with_bit_length(&mut rng, exponent_bits);
+    let context = Montgomery::new(modulus.clone()).unwrap();
+
+    println!("{}-bit modulus, {}-bit exponent", modulus_bits, exponent_bits);
+
+    let (naive, naive_time) = time(5, || base.modpow(&exponent, &modulus).unwrap());
+    let (fast, fast_time) = time(5, || context.element(&base).pow(&exponent).unwrap().to_bigint());
+    assert_eq!(naive, fast);
+    report("pow", naive_time, fast_time);
+
+    // a chain of multiplications, where the conversions in and out of Montgomery form are paid
+    // only once
+    let steps = 1000;
+    let (naive, naive_time) = time(5, || {
+        let mut acc = Bigint::one();
+        for _ in 0..steps {
+            acc = (acc * base.clone()) % modulus.clone();
+        }
+        acc
+    });
+    let (fast, fast_time) = time(5, || {
+        let element = context.element(&base);
+        let mut acc = context.one();
+        for _ in 0..steps {
+            acc = acc * element.clone();
+        }
+        acc.to_bigint()
+    });
+    assert_eq!(naive, fast);
+    report(&format!("{} muls", steps), naive_time, fast_time);
+}
@@ -0,0 +1,16 @@
+/// An interactive calculator over `Bigint`, reading expressions from stdin.
+///
+/// rustc calculator.rs && ./calculator
+///
+#[path = "homework_02.rs"]
+#[allow(dead_code)]
+mod homework_02;
+
+fn main() {
+    let stdin = std::io::stdin();
+
+    if let Err(error) = homework_02::run_repl(stdin.lock(), std::io::stdout()) {
+        eprintln!("{}", error);
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,377 @@
+/// Runs the differential fuzzing harness for `Bigint` with a seeded generator, or replays a single
+/// input saved by a `cargo fuzz` target.
+///
+/// rustc -O fuzz.rs && ./fuzz [seed] [iterations]
+/// rustc -O fuzz.rs && ./fuzz --replay <file>
+/// rustc --test fuzz.rs && ./fuzz
+///
+#[path = "homework_02.rs"]
+#[allow(dead_code)]
+mod homework_02;
+
+use homework_02::{Bigint, Rng, XorShift64};
+use std::cmp::Ordering;
+use std::fmt;
+use std::str::FromStr;
+
+/// A deliberately simple model of signed integers, used as an independent oracle by the fuzzing
+/// harness: little-endian decimal digits in `u32`, and the most direct algorithms possible.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct ReferenceInt {
+    negative: bool,
+    digits: Vec<u32>,
+}
+
+impl ReferenceInt {
+    fn normalized(negative: bool, mut digits: Vec<u32>) -> Self {
+        while digits.last() == Some(&0) {
+            digits.pop();
+        }
+
+        return Self { negative: negative && !digits.is_empty(), digits };
+    }
+
+    /// The grammar of `Bigint::from_str`: an optional sign, then only digits, possibly none.
+    fn parse(s: &str) -> Option<Self> {
+        let (negative, rest) = match s.as_bytes().first() {
+            Some(b'-') => (true, &s[1..]),
+            Some(b'+') => (false, &s[1..]),
+            _ => (false, s),
+        };
+
+        let mut digits = vec![];
+        for char in rest.chars().rev() {
+            digits.push(char.to_digit(10)?);
+        }
+
+        return Some(Self::normalized(negative, digits));
+    }
+
+    fn cmp_magnitude(&self, other: &Self) -> Ordering {
+        if self.digits.len() != other.digits.len() {
+            return self.digits.len().cmp(&other.digits.len());
+        }
+
+        for i in (0..self.digits.len()).rev() {
+            if self.digits[i] != other.digits[i] {
+                return self.digits[i].cmp(&other.digits[i]);
+            }
+        }
+
+        return Ordering::Equal;
+    }
+
+    fn cmp(&self, other: &Self) -> Ordering {
+        let sign = |value: &Self| if value.negative { -1 } else if value.digits.is_empty() { 0 } else { 1 };
+        if sign(self) != sign(other) {
+            return sign(self).cmp(&sign(other));
+        }
+
+        let by_magnitude = self.cmp_magnitude(other);
+
+        return if self.negative { by_magnitude.reverse() } else { by_magnitude };
+    }
+
+    fn add(&self, other: &Self) -> Self {
+        if self.negative == other.negative {
+            let mut digits = vec![];
+            let mut carrier = 0;
+
+            for i in 0..std::cmp::max(self.digits.len(), other.digits.len()) + 1 {
+                let sum = self.digits.get(i).unwrap_or(&0) + other.digits.get(i).unwrap_or(&0) + carrier;
+                digits.push(sum % 10);
+                carrier = sum / 10;
+            }
+
+            return Self::normalized(self.negative, digits);
+        }
+
+        let (larger, smaller) = if self.cmp_magnitude(other) == Ordering::Less { (other, self) } else { (self, other) };
+        let mut digits = vec![];
+        let mut borrow: i64 = 0;
+
+        for i in 0..larger.digits.len() {
+            let mut difference = larger.digits[i] as i64 - *smaller.digits.get(i).unwrap_or(&0) as i64 - borrow;
+            borrow = 0;
+            if difference < 0 {
+                difference += 10;
+                borrow = 1;
+            }
+
+            digits.push(difference as u32);
+        }
+
+        return Self::normalized(larger.negative, digits);
+    }
+
+    fn sub(&self, other: &Self) -> Self {
+        return self.add(&Self::normalized(!other.negative, other.digits.clone()));
+    }
+}
+
+impl fmt::Display for ReferenceInt {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.digits.is_empty() {
+            return write!(f, "0");
+        }
+
+        let digits: String = self.digits.iter().rev().map(|digit| char::from_digit(*digit, 10).unwrap()).collect();
+
+        return write!(f, "{}{}", if self.negative { "-" } else { "" }, digits);
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum FuzzOp {
+    Parse,
+    Add,
+    Sub,
+    Cmp,
+}
+
+/// A case where `Bigint` disagreed with `i128` or with the reference model.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct FuzzFailure {
+    op: FuzzOp,
+    left: String,
+    right: String,
+    expected: String,
+    actual: String,
+}
+
+impl fmt::Display for FuzzFailure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        return write!(
+            f, "{:?} on {:?} and {:?}: expected {}, got {}",
+            self.op, self.left, self.right, self.expected, self.actual,
+        );
+    }
+}
+
+fn check_result(op: FuzzOp, left: &str, right: &str, expected: String, actual: String) -> Result<(), FuzzFailure> {
+    if expected == actual {
+        return Ok(());
+    }
+
+    return Err(FuzzFailure { op, left: left.to_string(), right: right.to_string(), expected, actual });
+}
+
+/// Checks a single pair of inputs: parsing both, then `+`, `-` and `cmp` on them, against the
+/// reference model and, when everything fits, against `i128`. Inputs that neither side can parse
+/// are fine.
+fn fuzz_check(left: &str, right: &str) -> Result<(), FuzzFailure> {
+    let mut parsed = vec![];
+
+    for input in [left, right] {
+        let model = ReferenceInt::parse(input);
+        let actual = Bigint::from_str(input).ok();
+
+        check_result(
+            FuzzOp::Parse, left, right,
+            model.as_ref().map_or("an error".to_string(), |value| value.to_string()),
+            actual.as_ref().map_or("an error".to_string(), |value| value.to_string()),
+        )?;
+
+        match (model, actual) {
+            (Some(model), Some(actual)) => parsed.push((model, actual)),
+            _ => return Ok(()),
+        }
+    }
+
+    let (right_model, right_value) = parsed.pop().unwrap();
+    let (left_model, left_value) = parsed.pop().unwrap();
+    let left_native = i128::from_str(&left_model.to_string()).ok();
+    let right_native = i128::from_str(&right_model.to_string()).ok();
+
+    let sum = left_value.clone() + right_value.clone();
+    check_result(FuzzOp::Add, left, right, left_model.add(&right_model).to_string(), sum.to_string())?;
+    if let Some(native) = left_native.zip(right_native).and_then(|(l, r)| l.checked_add(r)) {
+        check_result(FuzzOp::Add, left, right, native.to_string(), sum.to_string())?;
+    }
+
+    let difference = left_value.clone() - right_value.clone();
+    check_result(FuzzOp::Sub, left, right, left_model.sub(&right_model).to_string(), difference.to_string())?;
+    if let Some(native) = left_native.zip(right_native).and_then(|(l, r)| l.checked_sub(r)) {
+        check_result(FuzzOp::Sub, left, right, native.to_string(), difference.to_string())?;
+    }
+
+    let order = left_value.cmp(&right_value);
+    check_result(FuzzOp::Cmp, left, right, format!("{:?}", left_model.cmp(&right_model)), format!("{:?}", order))?;
+    if let Some((l, r)) = left_native.zip(right_native) {
+        check_result(FuzzOp::Cmp, left, right, format!("{:?}", l.cmp(&r)), format!("{:?}", order))?;
+    }
+
+    return Ok(());
+}
+
+/// The body of a `cargo fuzz` target: splits raw bytes at the first `,` into two inputs.
+fn fuzz_bytes(data: &[u8]) -> Result<(), FuzzFailure> {
+    let text = String::from_utf8_lossy(data);
+    let (left, right) = text.split_once(',').unwrap_or((&text, ""));
+
+    return fuzz_check(left, right);
+}
+
+/// A random input: mostly valid numbers of very different lengths, with signs and leading zeros,
+/// sometimes with a stray character.
+fn random_fuzz_input<R: Rng>(rng: &mut R) -> String {
+    let mut res = String::new();
+
+    match rng.next_u64() % 4 {
+        0 => res.push('-'),
+        1 => res.push('+'),
+        _ => {},
+    }
+
+    for _ in 0..rng.next_u64() % 4 {
+        res.push('0');
+    }
+
+    let len = match rng.next_u64() % 4 {
+        0 => rng.next_u64() % 4,
+        1 | 2 => rng.next_u64() % 39,
+        _ => rng.next_u64() % 200,
+    };
+
+    for _ in 0..len {
+        // lots of nines and zeros, to exercise the carries and the borrows
+        let digit = match rng.next_u64() % 4 {
+            0 => 9,
+            1 => 0,
+            _ => rng.next_u64() % 10,
+        };
+        res.push(char::from_digit(digit as u32, 10).unwrap());
+    }
+
+    if rng.next_u64().is_multiple_of(50) {
+        let position = (rng.next_u64() as usize) % (res.len() + 1);
+        res.insert(position, ['x', ' ', '-', '+', '.'][(rng.next_u64() % 5) as usize]);
+    }
+
+    return res;
+}
+
+/// Shrinks a failing case, greedily dropping characters and turning digits into `0` and `1` for
+/// as long as it keeps failing.
+fn fuzz_minimize(failure: FuzzFailure) -> FuzzFailure {
+    return fuzz_minimize_with(failure, fuzz_check);
+}
+
+/// `fuzz_minimize` with another check in place of `fuzz_check`.
+fn fuzz_minimize_with<F>(failure: FuzzFailure, check: F) -> FuzzFailure
+    where F: Fn(&str, &str) -> Result<(), FuzzFailure>
+{
+    let mut current = failure;
+
+    loop {
+        let mut candidates: Vec<(String, String)> = vec![];
+
+        for (side, input) in [&current.left, &current.right].iter().enumerate() {
+            for (i, char) in input.char_indices() {
+                let mut shorter = input.to_string();
+                shorter.remove(i);
+                candidates.push(if side == 0 { (shorter, current.right.clone()) } else { (current.left.clone(), shorter) });
+
+                for simpler in ['0', '1'] {
+                    if char.is_ascii_digit() && char > simpler {
+                        let mut changed = input.to_string();
+                        changed.replace_range(i..i + 1, &simpler.to_string());
+                        candidates.push(if side == 0 { (changed, current.right.clone()) } else { (current.left.clone(), changed) });
+                    }
+                }
+            }
+        }
+
+        // carries usually need both sides to shrink together, aligned from the lowest digit
+        for from_end in 1..=std::cmp::min(current.left.len(), current.right.len()) {
+            let mut left = current.left.clone();
+            let mut right = current.right.clone();
+            if left.is_char_boundary(left.len() - from_end) && right.is_char_boundary(right.len() - from_end) {
+                left.remove(left.len() - from_end);
+                right.remove(right.len() - from_end);
+                candidates.push((left, right));
+            }
+        }
+
+        let smaller = candidates.iter().find_map(|(left, right)| check(left, right).err());
+        match smaller {
+            Some(failure) => current = failure,
+            None => return current,
+        }
+    }
+}
+
+/// Runs `iterations` random cases from a seeded generator. The first failure is minimized and
+/// returned.
+fn fuzz_run(seed: u64, iterations: usize) -> Result<(), FuzzFailure> {
+    let mut rng = XorShift64::new(seed);
+
+    for _ in 0..iterations {
+        let left = random_fuzz_input(&mut rng);
+        let right = random_fuzz_input(&mut rng);
+
+        if let Err(failure) = fuzz_check(&left, &right) {
+            return Err(fuzz_minimize(failure));
+        }
+    }
+
+    return Ok(());
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+
+    let res = if args.get(1).map(String::as_str) == Some("--replay") {
+        let path = args.get(2).expect("usage: fuzz --replay <file>");
+        let data = std::fs::read(path).unwrap_or_else(|err| panic!("cannot read {}: {}", path, err));
+        fuzz_bytes(&data).map(|()| println!("{} passed", path))
+    } else {
+        let seed = args.get(1).and_then(|arg| arg.parse().ok()).unwrap_or(1);
+        let iterations = args.get(2).and_then(|arg| arg.parse().ok()).unwrap_or(100_000);
+        fuzz_run(seed, iterations).map(|()| println!("{} cases passed with seed {}", iterations, seed))
+    };
+
+    if let Err(failure) = res {
+        println!("{}", failure);
+        std::process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fuzz_run_passes_with_a_fixed_seed() {
+        assert!(fuzz_run(41, 5000).is_ok());
+    }
+
+    /// Plays an `Add` that loses the carry out of the top digit, once both sides have two digits.
+    fn lost_top_carry(left: &str, right: &str) -> Result<(), FuzzFailure> {
+        let (Ok(l), Ok(r)) = (left.parse::<u64>(), right.parse::<u64>()) else {
+            return Ok(());
+        };
+        let width = std::cmp::max(l.to_string().len(), r.to_string().len());
+        let sum = (l + r).to_string();
+        if l < 10 || r < 10 || sum.len() <= width {
+            return Ok(());
+        }
+
+        return Err(FuzzFailure {
+            op: FuzzOp::Add,
+            left: left.to_string(),
+            right: right.to_string(),
+            expected: sum.clone(),
+            actual: sum[1..].to_string(),
+        });
+    }
+
+    #[test]
+    fn fuzz_minimize_shrinks_a_failing_pair() {
+        let failure = lost_top_carry("987654321", "456789123").unwrap_err();
+        let minimal = fuzz_minimize_with(failure, lost_top_carry);
+
+        assert_eq!((minimal.left.as_str(), minimal.right.as_str()), ("90", "10"));
+        assert_eq!((minimal.expected.as_str(), minimal.actual.as_str()), ("100", "00"));
+    }
+}
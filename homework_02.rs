@@ -1,40 +1,211 @@
 use std::cmp::*;
 use std::str::FromStr;
-use std::ops::{Add, Sub};
+use std::convert::TryFrom;
+use std::fmt;
+use std::iter::{Product, Sum};
+use std::hash::{Hash, Hasher};
+use std::ops::{Add, BitAnd, BitOr, BitXor, Deref, Div, Mul, Neg, Not, Rem, Shl, Shr, Sub};
 use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::io::{BufRead, Read, Write};
 
-#[derive(Debug, PartialEq, Eq)]
+/// The sign of a `Bigint`. Zero has its own variant, so there is exactly one way to write it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Sign {
+    Minus,
+    Zero,
+    Plus,
+}
+
+impl Sign {
+    fn inversed(self) -> Self {
+        match self {
+            Sign::Minus => Sign::Plus,
+            Sign::Zero => Sign::Zero,
+            Sign::Plus => Sign::Minus,
+        }
+    }
+}
+
+/// Magnitudes with up to this many digits are stored inline. All of them fit in a `u64`.
+const INLINE_DIGITS: usize = 19;
+
+/// The decimal digits of a magnitude, most significant first. Short ones live inside the value
+/// and only the long ones are on the heap, so most numbers never allocate. A magnitude is inline
+/// exactly when it's short enough, which keeps every number with a single representation.
+#[derive(Clone)]
+enum Digits {
+    Inline { len: u8, buf: [u8; INLINE_DIGITS] },
+    Heap(Vec<u8>),
+}
+
+impl Digits {
+    const fn empty() -> Self {
+        return Digits::Inline { len: 0, buf: [0; INLINE_DIGITS] };
+    }
+
+    /// Expects the digits without leading zeros.
+    fn from_vec(digits: Vec<u8>) -> Self {
+        if digits.len() > INLINE_DIGITS {
+            return Digits::Heap(digits);
+        }
+
+        let mut buf = [0; INLINE_DIGITS];
+        buf[..digits.len()].copy_from_slice(&digits);
+
+        return Digits::Inline { len: digits.len() as u8, buf };
+    }
+
+    fn from_u64(mut value: u64) -> Self {
+        if value >= 10_000_000_000_000_000_000 {
+            return Digits::from_vec(value.to_string().bytes().map(|byte| byte - b'0').collect());
+        }
+
+        // fill the buffer from the end, then move the digits to the front
+        let mut buf = [0; INLINE_DIGITS];
+        let mut start = INLINE_DIGITS;
+        while value > 0 {
+            start -= 1;
+            buf[start] = (value % 10) as u8;
+            value /= 10;
+        }
+
+        buf.copy_within(start.., 0);
+
+        return Digits::Inline { len: (INLINE_DIGITS - start) as u8, buf };
+    }
+
+    /// The value of an inline magnitude, `None` for the ones on the heap.
+    fn small_value(&self) -> Option<u64> {
+        return match self {
+            Digits::Inline { len, buf } => Some(buf[..*len as usize].iter().fold(0, |acc, &digit| acc * 10 + digit as u64)),
+            Digits::Heap(_) => None,
+        }
+    }
+}
+
+impl Deref for Digits {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        return match self {
+            Digits::Inline { len, buf } => &buf[..*len as usize],
+            Digits::Heap(digits) => digits,
+        }
+    }
+}
+
+impl PartialEq for Digits {
+    fn eq(&self, other: &Digits) -> bool {
+        return **self == **other;
+    }
+}
+
+impl Eq for Digits {}
+
+impl Hash for Digits {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        (**self).hash(state);
+    }
+}
+
+impl fmt::Debug for Digits {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        return fmt::Debug::fmt(&**self, f);
+    }
+}
+
+/// Invariant: `digits` has no leading zeros, and it is empty exactly when `sign` is `Sign::Zero`.
+/// Every number has a single representation, so the derived `PartialEq` and `Hash` agree.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct Bigint {
-    sign: i8,
-+
-    digits: Vec<u8>,
+    sign: Sign,
+    digits: Digits,
 }
 
 impl Bigint {
-    pub fn new() -> Self {
-        return Bigint { sign: i8::MAX, digits: Vec::new() };
+    pub const fn new() -> Self {
+        return Bigint { sign: Sign::Zero, digits: Digits::empty() };
+    }
+
+    /// Builds a canonical value -- strips the leading zeros and fixes the sign of zero.
+    fn from_parts(sign: Sign, mut digits: Vec<u8>) -> Self {
+        strip_leading_zeros(&mut digits);
+
+        if digits.is_empty() {
+            return Bigint::new();
+        }
+
+        return Self {
+            sign: if sign == Sign::Zero { Sign::Plus } else { sign },
+            digits: Digits::from_vec(digits),
+        }
+    }
+
+    fn from_u64(value: u64) -> Self {
+        return Self {
+            sign: if value == 0 { Sign::Zero } else { Sign::Plus },
+            digits: Digits::from_u64(value),
+        }
+    }
+
+    /// Never allocates when the magnitude is short enough to be inline.
+    fn from_i128(value: i128) -> Self {
+        let magnitude = value.unsigned_abs();
+
+        if magnitude > u64::MAX as u128 {
+            return Bigint::from_str(&value.to_string()).unwrap();
+        }
+
+        let res = Bigint::from_u64(magnitude as u64);
+
+        return if value < 0 { res.get_inversed() } else { res };
+    }
+
+    /// The value, if the magnitude is stored inline. This is the fast path of the arithmetic.
+    fn small_value(&self) -> Option<i128> {
+        let magnitude = self.digits.small_value()? as i128;
+
+        return Some(if self.is_negative() { -magnitude } else { magnitude });
     }
 
     fn get_inversed(&self) -> Self {
         Self {
-            sign: if self.sign == i8::MAX {i8::MIN} else {i8::MAX},
+            sign: self.sign.inversed(),
             digits: self.digits.clone(),
         }
     }
 
-    fn get_abs(&self) -> Self {
+    pub fn zero() -> Self {
+        return Bigint::new();
+    }
+
+    pub fn one() -> Self {
+        return Bigint::from_u64(1);
+    }
+
+    pub fn abs(&self) -> Self {
         Self {
-            sign: i8::MAX,
+            sign: if self.sign == Sign::Zero { Sign::Zero } else { Sign::Plus },
             digits: self.digits.clone(),
         }
     }
 
+    /// -1, 0 or 1, depending on the sign.
+    pub fn signum(&self) -> Self {
+        return Bigint::from_parts(self.sign, if self.sign == Sign::Zero { vec![] } else { vec![1] });
+    }
+
     fn get_size(&self) -> usize {
         self.digits.len()
     }
 
     fn get_sign_as_char(&self) -> char {
-        return if self.sign == i8::MAX { '+' } else { '-' }
+        return if self.sign == Sign::Minus { '-' } else { '+' }
+    }
+
+    pub fn sign(&self) -> Sign {
+        return self.sign;
     }
 
     pub fn print(&self) {
@@ -42,55 +213,118 @@ impl Bigint {
     }
 
     pub fn is_positive(&self) -> bool {
-        return self.sign == i8::MAX;
+        return self.sign == Sign::Plus;
     }
 
     pub fn is_negative(&self) -> bool {
-        return self.sign == i8::MIN;
+        return self.sign == Sign::Minus;
+    }
+
+    pub fn is_zero(&self) -> bool {
+        return self.sign == Sign::Zero;
+    }
+
+    pub fn is_one(&self) -> bool {
+        return self.sign == Sign::Plus && *self.digits == [1];
+    }
+}
+
+impl Default for Bigint {
+    fn default() -> Self {
+        return Bigint::new();
+    }
+}
+
+fn strip_leading_zeros(digits: &mut Vec<u8>) {
+    let zeros = digits.iter().take_while(|&&digit| digit == 0).count();
+    digits.drain(..zeros);
+}
+
+/// Compares two magnitudes, both without leading zeros.
+fn cmp_digits(left: &[u8], right: &[u8]) -> Ordering {
+    if left.len() != right.len() {
+        return left.len().cmp(&right.len());
     }
+
+    return left.cmp(right);
 }
 
 #[derive(Debug)]
 pub struct ParseError;
 
+#[derive(Debug)]
+pub enum DecodeError {
+    IO(std::io::Error),
+    Truncated,
+    LengthOverflow,
+    /// A character that doesn't belong in a number, at this byte offset of the input.
+    InvalidDigit(u64),
+}
+
+impl From<std::io::Error> for DecodeError {
+    fn from(error: std::io::Error) -> Self {
+        DecodeError::IO(error)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ArithmeticError {
+    DivideByZero,
+    NonPositiveModulus,
+    NotInvertible,
+    NegativeRoot,
+    ZeroRootDegree,
+    NonPositiveLogarithm,
+    InvalidLogarithmBase,
+    EmptyRange,
+    Overflow,
+    NoSolution,
+}
+
+impl fmt::Display for ArithmeticError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let message = match self {
+            ArithmeticError::DivideByZero => "division by zero",
+            ArithmeticError::NonPositiveModulus => "the modulus has to be positive",
+            ArithmeticError::NotInvertible => "the number is not invertible",
+            ArithmeticError::NegativeRoot => "even root of a negative number",
+            ArithmeticError::ZeroRootDegree => "root of degree zero",
+            ArithmeticError::NonPositiveLogarithm => "logarithm of a non-positive number",
+            ArithmeticError::InvalidLogarithmBase => "the logarithm base has to be at least 2",
+            ArithmeticError::EmptyRange => "the range is empty",
+            ArithmeticError::Overflow => "the value doesn't fit in the type",
+            ArithmeticError::NoSolution => "the congruences have no solution",
+        };
+
+        return f.write_str(message);
+    }
+}
+
 impl FromStr for Bigint {
     type Err = ParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut digits: Vec<u8> = Vec::new();
-        let mut sign: i8 = i8::MAX;
-        let mut init_index = 0;
-        let chars: Vec<char> = s.chars().collect();
-
-        if chars.len() != 0 {
-            if chars[0] == '+' {
-                sign = i8::MAX;
-                init_index = 1;
-            } else if chars[0] == '-' {
-                sign = i8::MIN;
-                init_index = 1;
-            }
+        let (sign, unsigned) = split_sign(s);
+        let bytes = unsigned.as_bytes();
 
-            for i in init_index..chars.len() {
-                let char = chars[i];
+        if !bytes.iter().all(|byte| byte.is_ascii_digit()) {
+            return Err(ParseError);
+        }
 
-                if !char.is_digit(10) {
-                    return Err(ParseError);
-                }
+        let zeros = bytes.iter().take_while(|&&byte| byte == b'0').count();
+        let significant = &bytes[zeros..];
 
-                digits.push(char.to_digit(10).unwrap() as u8)
-            }
+        // short numbers are read straight into a `u64`, without allocating
+        if significant.len() <= INLINE_DIGITS {
+            let value = significant.iter().fold(0, |acc, &byte| acc * 10 + (byte - b'0') as u64);
+            let res = Bigint::from_u64(value);
 
-            while !digits.is_empty() && digits[0] == 0 {
-                digits.remove(0);
-            }
+            return Ok(if sign == Sign::Minus { res.get_inversed() } else { res });
         }
 
-        if digits.len() == 0 {
-            sign = i8::MAX;
-        }
+        let digits: Vec<u8> = significant.iter().map(|byte| byte - b'0').collect();
 
-        Ok(Bigint { digits, sign })
+        Ok(Bigint::from_parts(sign, digits))
     }
 }
 
@@ -103,32 +337,36 @@ impl PartialOrd for Bigint {
 impl Ord for Bigint {
     fn cmp(&self, other: &Bigint) -> Ordering {
         if self.sign != other.sign {
-            if self.sign < other.sign {
-                return Ordering::Less;
-            } else if self.sign > other.sign {
-                return Ordering::Greater;
-            }
+            return self.sign.cmp(&other.sign);
+        }
+
+        if let (Some(left), Some(right)) = (self.small_value(), other.small_value()) {
+            return left.cmp(&right);
         }
 
-        let is_negative: bool = self.is_negative();
+        let by_magnitude = cmp_digits(&self.digits, &other.digits);
 
-        if self.get_size() != other.get_size() {
-            if self.get_size() < other.get_size() {
-                return if is_negative { Ordering::Greater } else { Ordering::Less };
-            } else if self.get_size() > other.get_size() {
-                return if is_negative { Ordering::Less } else { Ordering::Greater };
-            }
+        return if self.is_negative() { by_magnitude.reverse() } else { by_magnitude };
+    }
+}
+
+impl fmt::Display for Bigint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.sign == Sign::Zero {
+            return f.pad_integral(true, "", "0");
         }
 
-        return self.digits.cmp(&other.digits);
+        let digits: String = self.digits.iter().map(|&digit| (b'0' + digit) as char).collect();
+
+        return f.pad_integral(!self.is_negative(), "", &digits);
     }
 }
 
-fn add_digits(mut left: Vec<u8>, mut right: Vec<u8>) -> Vec<u8> {
+fn add_digits(left: &[u8], right: &[u8]) -> Vec<u8> {
     let mut res: Vec<u8> = Vec::new();
     let bigger_length = std::cmp::max(left.len(), right.len());
-    let mut left_reversed: Vec<u8> = left.clone();
-    let mut right_reversed: Vec<u8> = right.clone();
+    let mut left_reversed: Vec<u8> = left.to_vec();
+    let mut right_reversed: Vec<u8> = right.to_vec();
 
     left_reversed.reverse();
     right_reversed.reverse();
@@ -138,8 +376,10 @@ fn add_digits(mut left: Vec<u8>, mut right: Vec<u8>) -> Vec<u8> {
 
     let mut carrier:u8 = 0;
     for i in 0..bigger_length {
-        res.push((left_reversed[i] + right_reversed[i] + carrier.clone())  % 10);
-        carrier = (left_reversed[i] + right_reversed[i] + carrier) / 10;
+        let sum: u8 = left_reversed[i] + right_reversed[i] + carrier;
+
+        res.push(sum % 10);
+        carrier = sum / 10;
     }
 
     if carrier != 0 {
@@ -151,11 +391,12 @@ fn add_digits(mut left: Vec<u8>, mut right: Vec<u8>) -> Vec<u8> {
     return res;
 }
 
-fn subtract_digits(mut larger: Vec<u8>, mut smaller: Vec<u8>) -> Vec<u8>  {
+/// Expects `larger >= smaller` as magnitudes.
+fn subtract_digits(larger: &[u8], smaller: &[u8]) -> Vec<u8>  {
     let mut res: Vec<u8> = Vec::new();
     let bigger_length = std::cmp::max(larger.len(), smaller.len());
-    let mut larger_reversed: Vec<u8> = larger.clone();
-    let mut smaller_reversed: Vec<u8> = smaller.clone();
+    let mut larger_reversed: Vec<u8> = larger.to_vec();
+    let mut smaller_reversed: Vec<u8> = smaller.to_vec();
 
     larger_reversed.reverse();
     smaller_reversed.reverse();
@@ -165,18 +406,17 @@ fn subtract_digits(mut larger: Vec<u8>, mut smaller: Vec<u8>) -> Vec<u8>  {
 
     let mut carrier:u8 = 0;
     for i in 0..bigger_length {
-        let sub: u8 = 10 + larger_reversed[i] - smaller_reversed[i];
+        // 10 + l - s - carrier is always in 0..=19, so it never underflows
+        let sub: u8 = 10 + larger_reversed[i] - smaller_reversed[i] - carrier;
 
-        res.push((sub.clone() - carrier.clone()) % 10);
-        carrier = ((sub - carrier) < 10) as u8
+        res.push(sub % 10);
+        carrier = (sub < 10) as u8
     }
 
     res.reverse();
 
     // remove padding
-    while !res.is_empty() && res[0] == 0 {
-        res.remove(0);
-    }
+    strip_leading_zeros(&mut res);
 
     return res;
 }
@@ -185,21 +425,26 @@ impl Add for Bigint {
     type Output = Bigint;
 
     fn add(self, other: Self) -> Self {
-        if self.sign == other.sign {
-            return Self {
-                sign: self.sign.clone(),
-                digits: add_digits(self.digits, other.digits),
-            }
+        if self.is_zero() {
+            return other;
         }
 
-        let bigger: Vec<u8> = if self.get_abs() > other.get_abs() { self.digits.clone() } else { other.digits.clone() };
-        let smaller: Vec<u8> = if self.get_abs() < other.get_abs() { self.digits.clone() } else { other.digits.clone() };
+        if other.is_zero() {
+            return self;
+        }
 
-        let res: Vec<u8> = subtract_digits(bigger, smaller);
+        if let (Some(left), Some(right)) = (self.small_value(), other.small_value()) {
+            return Bigint::from_i128(left + right);
+        }
 
-        return Self {
-            sign: if res.is_empty() { i8::MAX } else if self.get_abs() > other.get_abs() { self.sign.clone() } else { other.sign.clone() },
-            digits: res,
+        if self.sign == other.sign {
+            return Bigint::from_parts(self.sign, add_digits(&self.digits, &other.digits));
+        }
+
+        return match cmp_digits(&self.digits, &other.digits) {
+            Ordering::Equal => Bigint::new(),
+            Ordering::Greater => Bigint::from_parts(self.sign, subtract_digits(&self.digits, &other.digits)),
+            Ordering::Less => Bigint::from_parts(other.sign, subtract_digits(&other.digits, &self.digits)),
         }
     }
 }
@@ -208,14 +453,4498 @@ impl Sub for Bigint {
     type Output = Bigint;
 
     fn sub(self, other: Self) -> Self {
-        if self.sign != other.sign {
-            return Self {
-                sign: self.sign.clone(),
-                digits: add_digits(self.get_inversed().digits, other.digits)
-            }
+        if let (Some(left), Some(right)) = (self.small_value(), other.small_value()) {
+            return Bigint::from_i128(left - right);
         }
 
         return self + other.get_inversed()
     }
 }
 
+fn mul_digits(left: &[u8], right: &[u8]) -> Vec<u8> {
+    if left.is_empty() || right.is_empty() {
+        return Vec::new();
+    }
+
+    // accumulate the column sums from the least significant end, then carry once
+    let mut columns: Vec<u32> = vec![0; left.len() + right.len()];
+    for (i, &l) in left.iter().rev().enumerate() {
+        for (j, &r) in right.iter().rev().enumerate() {
+            columns[i + j] += l as u32 * r as u32;
+        }
+    }
+
+    let mut res: Vec<u8> = Vec::with_capacity(columns.len());
+    let mut carrier: u32 = 0;
+    for column in columns {
+        let sum = column + carrier;
+        res.push((sum % 10) as u8);
+        carrier = sum / 10;
+    }
+
+    res.reverse();
+    strip_leading_zeros(&mut res);
+
+    return res;
+}
+
+/// The number written by a few leading digits, at most 18 of them so that it fits in a `u64`.
+fn leading_value(digits: &[u8]) -> u64 {
+    return digits.iter().fold(0, |acc, &digit| acc * 10 + digit as u64);
+}
+
+/// Long division of two magnitudes. Returns the quotient and the remainder, `divisor` must not be
+/// empty.
+fn divmod_digits(dividend: &[u8], divisor: &[u8]) -> (Vec<u8>, Vec<u8>) {
+    let mut quotient: Vec<u8> = Vec::with_capacity(dividend.len());
+    let mut remainder: Vec<u8> = Vec::new();
+    // the leading digits of the divisor, to estimate each digit of the quotient from
+    let top = std::cmp::min(divisor.len(), 17);
+    let divisor_top = leading_value(&divisor[..top]);
+
+    for &digit in dividend {
+        remainder.push(digit);
+        strip_leading_zeros(&mut remainder);
+
+        if cmp_digits(&remainder, divisor) == Ordering::Less {
+            quotient.push(0);
+            continue;
+        }
+
+        // the remainder has at most one digit more than the divisor, and rounding its leading
+        // digits up gives an estimate that is never too small and almost never too big
+        let extra = remainder.len() - divisor.len();
+        let remainder_top = leading_value(&remainder[..top + extra]);
+        let mut times = std::cmp::min(9, (remainder_top + 1) / divisor_top) as u8;
+        let mut product = mul_digits(divisor, &[times]);
+
+        while cmp_digits(&product, &remainder) == Ordering::Greater {
+            times -= 1;
+            product = subtract_digits(&product, divisor);
+        }
+
+        remainder = subtract_digits(&remainder, &product);
+        quotient.push(times);
+    }
+
+    strip_leading_zeros(&mut quotient);
+
+    return (quotient, remainder);
+}
+
+/// Divides a magnitude by a single small number, returning the quotient and the remainder.
+fn divmod_small(dividend: &[u8], divisor: u32) -> (Vec<u8>, u32) {
+    let mut quotient: Vec<u8> = Vec::with_capacity(dividend.len());
+    let mut remainder: u32 = 0;
+
+    for &digit in dividend {
+        let current = remainder * 10 + digit as u32;
+        quotient.push((current / divisor) as u8);
+        remainder = current % divisor;
+    }
+
+    strip_leading_zeros(&mut quotient);
+
+    return (quotient, remainder);
+}
+
+impl Mul for Bigint {
+    type Output = Bigint;
+
+    fn mul(self, other: Self) -> Self {
+        // two inline magnitudes are below 10^19 each, so the product fits in an `i128`
+        if let (Some(left), Some(right)) = (self.small_value(), other.small_value()) {
+            return Bigint::from_i128(left * right);
+        }
+
+        let sign = if self.sign == other.sign { Sign::Plus } else { Sign::Minus };
+
+        return Bigint::from_parts(sign, mul_digits(&self.digits, &other.digits));
+    }
+}
+
+/// Truncating division, like the one on the primitive integers. Panics on division by zero.
+impl Div for Bigint {
+    type Output = Bigint;
+
+    fn div(self, other: Self) -> Self {
+        return self.div_rem(&other).expect("attempt to divide by zero").0;
+    }
+}
+
+/// The remainder has the sign of the dividend, like `%` on the primitive integers. Panics on
+/// division by zero.
+impl Rem for Bigint {
+    type Output = Bigint;
+
+    fn rem(self, other: Self) -> Self {
+        return self.div_rem(&other).expect("attempt to calculate the remainder with a divisor of zero").1;
+    }
+}
+
+impl Sum for Bigint {
+    fn sum<I: Iterator<Item = Bigint>>(iter: I) -> Self {
+        return iter.fold(Bigint::zero(), |acc, value| acc + value);
+    }
+}
+
+impl<'a> Sum<&'a Bigint> for Bigint {
+    fn sum<I: Iterator<Item = &'a Bigint>>(iter: I) -> Self {
+        return iter.fold(Bigint::zero(), |acc, value| acc + value.clone());
+    }
+}
+
+impl Product for Bigint {
+    fn product<I: Iterator<Item = Bigint>>(iter: I) -> Self {
+        return iter.fold(Bigint::one(), |acc, value| acc * value);
+    }
+}
+
+impl<'a> Product<&'a Bigint> for Bigint {
+    fn product<I: Iterator<Item = &'a Bigint>>(iter: I) -> Self {
+        return iter.fold(Bigint::one(), |acc, value| acc * value.clone());
+    }
+}
+
+impl Bigint {
+    fn from_u32(value: u32) -> Self {
+        return Bigint::from_u64(value as u64);
+    }
+
+    /// Truncating division with remainder: `self == quotient * other + remainder`, where the
+    /// remainder has the sign of `self` and is smaller than `other` by absolute value.
+    pub fn div_rem(&self, other: &Bigint) -> Result<(Bigint, Bigint), ArithmeticError> {
+        if other.is_zero() {
+            return Err(ArithmeticError::DivideByZero);
+        }
+
+        let (quotient, remainder) = divmod_digits(&self.digits, &other.digits);
+        let quotient_sign = if self.sign == other.sign { Sign::Plus } else { Sign::Minus };
+
+        return Ok((
+            Bigint::from_parts(quotient_sign, quotient),
+            Bigint::from_parts(self.sign, remainder),
+        ));
+    }
+
+    /// The least non-negative remainder of `self` modulo `modulus`, `modulus` has to be positive.
+    pub fn modulo(&self, modulus: &Bigint) -> Result<Bigint, ArithmeticError> {
+        if !modulus.is_positive() {
+            return Err(ArithmeticError::NonPositiveModulus);
+        }
+
+        let (_, remainder) = self.div_rem(modulus)?;
+
+        return if remainder.is_negative() { Ok(remainder + modulus.clone()) } else { Ok(remainder) };
+    }
+
+    pub fn pow(&self, mut exp: u32) -> Bigint {
+        let mut res = Bigint::from_u32(1);
+        let mut base = self.clone();
+
+        // square and multiply, going through the bits of `exp` from the lowest one
+        while exp > 0 {
+            if exp & 1 == 1 {
+                res = res * base.clone();
+            }
+
+            exp >>= 1;
+            if exp > 0 {
+                base = base.clone() * base;
+            }
+        }
+
+        return res;
+    }
+
+    /// `self` to the power of `exp`, modulo `modulus`. The result is in `0..modulus`.
+    ///
+    /// A negative `exp` raises the modular inverse of `self` instead, so it fails with
+    /// `ArithmeticError::NotInvertible` if there isn't one.
+    pub fn modpow(&self, exp: &Bigint, modulus: &Bigint) -> Result<Bigint, ArithmeticError> {
+        let mut base = if exp.is_negative() { self.mod_inverse(modulus)? } else { self.modulo(modulus)? };
+        let mut exp_digits = exp.digits.to_vec();
+        let mut res = Bigint::from_u32(1).modulo(modulus)?;
+
+        while !exp_digits.is_empty() {
+            let (halved, bit) = divmod_small(&exp_digits, 2);
+
+            if bit == 1 {
+                res = (res * base.clone()).modulo(modulus)?;
+            }
+
+            exp_digits = halved;
+            if !exp_digits.is_empty() {
+                base = (base.clone() * base).modulo(modulus)?;
+            }
+        }
+
+        return Ok(res);
+    }
+
+    /// The greatest common divisor, always non-negative. `gcd(0, 0)` is 0.
+    pub fn gcd(&self, other: &Bigint) -> Bigint {
+        let mut a = self.abs();
+        let mut b = other.abs();
+
+        while !b.is_zero() {
+            let remainder = (a % b.clone()).abs();
+            a = b;
+            b = remainder;
+        }
+
+        return a;
+    }
+
+    /// The least common multiple, always non-negative. It's 0 if any of the numbers is 0.
+    pub fn lcm(&self, other: &Bigint) -> Bigint {
+        if self.is_zero() || other.is_zero() {
+            return Bigint::new();
+        }
+
+        return (self.abs() / self.gcd(other)) * other.abs();
+    }
+
+    /// The extended Euclidean algorithm. Returns `(g, x, y)`, such that `self * x + other * y == g`
+    /// and `g` is the non-negative greatest common divisor.
+    pub fn extended_gcd(&self, other: &Bigint) -> (Bigint, Bigint, Bigint) {
+        let (mut old_r, mut r) = (self.clone(), other.clone());
+        let (mut old_x, mut x) = (Bigint::from_u32(1), Bigint::new());
+        let (mut old_y, mut y) = (Bigint::new(), Bigint::from_u32(1));
+
+        while !r.is_zero() {
+            let (quotient, remainder) = old_r.div_rem(&r).unwrap();
+
+            old_r = r;
+            r = remainder;
+
+            let next_x = old_x - quotient.clone() * x.clone();
+            old_x = x;
+            x = next_x;
+
+            let next_y = old_y - quotient * y.clone();
+            old_y = y;
+            y = next_y;
+        }
+
+        if old_r.is_negative() {
+            return (old_r.get_inversed(), old_x.get_inversed(), old_y.get_inversed());
+        }
+
+        return (old_r, old_x, old_y);
+    }
+
+    /// The `x` in `0..modulus` for which `self * x` is 1 modulo `modulus`.
+    pub fn mod_inverse(&self, modulus: &Bigint) -> Result<Bigint, ArithmeticError> {
+        let reduced = self.modulo(modulus)?;
+        let (g, x, _) = reduced.extended_gcd(modulus);
+
+        if !g.is_one() {
+            return Err(ArithmeticError::NotInvertible);
+        }
+
+        return x.modulo(modulus);
+    }
+}
+
+impl Bigint {
+    fn power_of_ten(exp: usize) -> Self {
+        let mut digits = vec![0; exp + 1];
+        digits[0] = 1;
+
+        return Bigint::from_parts(Sign::Plus, digits);
+    }
+
+    /// The number of bits needed to write the absolute value, 0 for zero.
+    pub fn bits(&self) -> u64 {
+        let limbs = to_binary_limbs(&self.digits);
+
+        return match limbs.last() {
+            Some(top) => limbs.len() as u64 * 16 - top.leading_zeros() as u64,
+            None => 0,
+        }
+    }
+
+    pub fn ilog2(&self) -> Result<u64, ArithmeticError> {
+        if !self.is_positive() {
+            return Err(ArithmeticError::NonPositiveLogarithm);
+        }
+
+        return Ok(self.bits() - 1);
+    }
+
+    pub fn ilog10(&self) -> Result<u64, ArithmeticError> {
+        if !self.is_positive() {
+            return Err(ArithmeticError::NonPositiveLogarithm);
+        }
+
+        return Ok(self.get_size() as u64 - 1);
+    }
+
+    /// The largest `k`, for which `base` to the power of `k` is not greater than `self`.
+    pub fn ilog(&self, base: &Bigint) -> Result<u64, ArithmeticError> {
+        if !self.is_positive() {
+            return Err(ArithmeticError::NonPositiveLogarithm);
+        }
+
+        if *base < Bigint::from_u32(2) {
+            return Err(ArithmeticError::InvalidLogarithmBase);
+        }
+
+        let mut res: u64 = 0;
+        let mut current = self.clone();
+        while current >= *base {
+            current = current / base.clone();
+            res += 1;
+        }
+
+        return Ok(res);
+    }
+
+    /// The floor of the square root.
+    pub fn sqrt(&self) -> Result<Bigint, ArithmeticError> {
+        return self.nth_root(2);
+    }
+
+    /// The `k`-th root, rounded towards zero. Odd roots of negative numbers are negative, even ones
+    /// are an error.
+    pub fn nth_root(&self, k: u32) -> Result<Bigint, ArithmeticError> {
+        if k == 0 {
+            return Err(ArithmeticError::ZeroRootDegree);
+        }
+
+        if self.is_negative() {
+            if k.is_multiple_of(2) {
+                return Err(ArithmeticError::NegativeRoot);
+            }
+
+            return Ok(self.abs().nth_root(k)?.get_inversed());
+        }
+
+        if self.is_zero() || k == 1 {
+            return Ok(self.clone());
+        }
+
+        // below 2^k the root is 1, and Newton's method would only build huge powers to find that
+        let bits = self.bits();
+        if k as u64 >= bits {
+            return Ok(Bigint::one());
+        }
+
+        // Newton's method, starting from a power of two that is surely above the root; the
+        // iterations go down monotonically until they reach the floor of the root
+        let degree = Bigint::from_u32(k);
+        let degree_less = Bigint::from_u32(k - 1);
+        let mut current = Bigint::one() << bits.div_ceil(k as u64) as u32;
+
+        loop {
+            let next = (degree_less.clone() * current.clone()
+                + self.clone() / current.pow(k - 1)) / degree.clone();
+
+            if next >= current {
+                return Ok(current);
+            }
+
+            current = next;
+        }
+    }
+
+    pub fn is_perfect_square(&self) -> bool {
+        return match self.sqrt() {
+            Ok(root) => root.clone() * root == *self,
+            Err(_) => false,
+        }
+    }
+}
+
+/// A source of random 64-bit words, for generating random `Bigint` values.
+pub trait Rng {
+    fn next_u64(&mut self) -> u64;
+}
+
+/// Marsaglia's xorshift64 -- small, fast and fully determined by its seed, which makes it good for
+/// tests and fuzzing. Not suitable for cryptography.
+#[derive(Debug, Clone)]
+pub struct XorShift64 {
+    state: u64,
+}
+
+impl XorShift64 {
+    /// A zero seed would get the generator stuck at zero, so it is replaced with a fixed constant.
+    pub fn new(seed: u64) -> Self {
+        return Self { state: if seed == 0 { 0x9E37_79B9_7F4A_7C15 } else { seed } };
+    }
+}
+
+impl Rng for XorShift64 {
+    fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+
+        return self.state;
+    }
+}
+
+impl Bigint {
+    /// A uniformly random number in `0..2^bits`.
+    pub fn random_bits<R: Rng>(rng: &mut R, bits: u64) -> Bigint {
+        let mut limbs: Vec<u16> = Vec::new();
+        let mut word: u64 = 0;
+
+        for i in 0..bits.div_ceil(16) {
+            if i % 4 == 0 {
+                word = rng.next_u64();
+            }
+
+            limbs.push(word as u16);
+            word >>= 16;
+        }
+
+        // mask the bits above `bits` in the top limb
+        if !bits.is_multiple_of(16) {
+            let last = limbs.len() - 1;
+            limbs[last] &= (1u16 << (bits % 16)) - 1;
+        }
+
+        return Bigint::from_parts(Sign::Plus, from_binary_limbs(&limbs));
+    }
+
+    /// A uniformly random number with exactly `bits` bits, i.e. in `2^(bits - 1)..2^bits`. Zero
+    /// bits give zero.
+    pub fn random_with_bit_length<R: Rng>(rng: &mut R, bits: u64) -> Bigint {
+        if bits == 0 {
+            return Bigint::zero();
+        }
+
+        let mut res = Bigint::random_bits(rng, bits - 1);
+        res.set_bit(bits - 1, true);
+
+        return res;
+    }
+
+    /// A uniformly random number in `0..bound`. Fails with `ArithmeticError::EmptyRange` if
+    /// `bound` is not positive.
+    pub fn random_below<R: Rng>(rng: &mut R, bound: &Bigint) -> Result<Bigint, ArithmeticError> {
+        if !bound.is_positive() {
+            return Err(ArithmeticError::EmptyRange);
+        }
+
+        // rejection sampling -- every try succeeds with probability above 1/2
+        let bits = bound.bits();
+        loop {
+            let candidate = Bigint::random_bits(rng, bits);
+
+            if candidate < *bound {
+                return Ok(candidate);
+            }
+        }
+    }
+
+    /// A uniformly random number in `low..high`. Fails with `ArithmeticError::EmptyRange` if
+    /// `low >= high`.
+    pub fn random_range<R: Rng>(rng: &mut R, low: &Bigint, high: &Bigint) -> Result<Bigint, ArithmeticError> {
+        let offset = Bigint::random_below(rng, &(high.clone() - low.clone()))?;
+
+        return Ok(offset + low.clone());
+    }
+}
+
+const SMALL_PRIMES: [u32; 12] = [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37];
+
+fn mul_mod_u64(a: u64, b: u64, modulus: u64) -> u64 {
+    return ((a as u128 * b as u128) % modulus as u128) as u64;
+}
+
+fn pow_mod_u64(mut base: u64, mut exp: u64, modulus: u64) -> u64 {
+    let mut res: u64 = 1 % modulus;
+    base %= modulus;
+
+    while exp > 0 {
+        if exp & 1 == 1 {
+            res = mul_mod_u64(res, base, modulus);
+        }
+
+        base = mul_mod_u64(base, base, modulus);
+        exp >>= 1;
+    }
+
+    return res;
+}
+
+impl Bigint {
+    fn to_u64(&self) -> Option<u64> {
+        if self.is_negative() {
+            return None;
+        }
+
+        let mut res: u64 = 0;
+        for &digit in self.digits.iter() {
+            res = res.checked_mul(10)?.checked_add(digit as u64)?;
+        }
+
+        return Some(res);
+    }
+
+    /// Splits `self - 1` into `d * 2^s` with an odd `d`. Expects an odd `self` greater than 2.
+    fn split_for_miller_rabin(&self) -> (Bigint, u32) {
+        let mut d = (self.clone() - Bigint::from_u32(1)).digits.to_vec();
+        let mut s: u32 = 0;
+
+        loop {
+            let (halved, remainder) = divmod_small(&d, 2);
+            if remainder == 1 {
+                break;
+            }
+
+            d = halved;
+            s += 1;
+        }
+
+        return (Bigint::from_parts(Sign::Plus, d), s);
+    }
+
+    /// A single Miller-Rabin round, `true` means that `base` doesn't witness that `self` is
+    /// composite.
+    fn passes_miller_rabin(&self, base: &Bigint, d: &Bigint, s: u32) -> bool {
+        let one = Bigint::from_u32(1);
+        let minus_one = self.clone() - one.clone();
+        let mut x = base.modpow(d, self).unwrap();
+
+        if x == one || x == minus_one {
+            return true;
+        }
+
+        for _ in 1..s {
+            x = (x.clone() * x).modulo(self).unwrap();
+            if x == minus_one {
+                return true;
+            }
+        }
+
+        return false;
+    }
+
+    /// An exact primality check for numbers below 2^64 -- Miller-Rabin with the first twelve
+    /// primes as bases has no false positives in that range. Returns `None` for bigger numbers.
+    pub fn is_prime_deterministic(&self) -> Option<bool> {
+        if self.is_negative() {
+            return Some(false);
+        }
+
+        let n = self.to_u64()?;
+        if n < 2 {
+            return Some(false);
+        }
+
+        for &prime in SMALL_PRIMES.iter() {
+            if n % prime as u64 == 0 {
+                return Some(n == prime as u64);
+            }
+        }
+
+        let d = (n - 1) >> (n - 1).trailing_zeros();
+        let s = (n - 1).trailing_zeros();
+
+        for &base in SMALL_PRIMES.iter() {
+            let mut x = pow_mod_u64(base as u64, d, n);
+            if x == 1 || x == n - 1 {
+                continue;
+            }
+
+            let mut witness = true;
+            for _ in 1..s {
+                x = mul_mod_u64(x, x, n);
+                if x == n - 1 {
+                    witness = false;
+                    break;
+                }
+            }
+
+            if witness {
+                return Some(false);
+            }
+        }
+
+        return Some(true);
+    }
+
+    /// Miller-Rabin with `rounds` pseudo-random bases. A composite number passes with probability
+    /// at most `4^-rounds`, a prime always passes. Numbers below 2^64 are checked exactly.
+    pub fn is_probable_prime(&self, rounds: u32) -> bool {
+        if let Some(res) = self.is_prime_deterministic() {
+            return res;
+        }
+
+        for &prime in SMALL_PRIMES.iter() {
+            if divmod_small(&self.digits, prime).1 == 0 {
+                return false;
+            }
+        }
+
+        let (d, s) = self.split_for_miller_rabin();
+        let low = Bigint::from_u32(2);
+        let high = self.clone() - Bigint::one();
+        // a constant seed, so the test gives the same answer on every run
+        let mut rng = XorShift64::new(0x2545_F491_4F6C_DD1D);
+
+        for _ in 0..rounds {
+            let base = Bigint::random_range(&mut rng, &low, &high).unwrap();
+
+            if !self.passes_miller_rabin(&base, &d, s) {
+                return false;
+            }
+        }
+
+        return true;
+    }
+
+    /// The smallest prime strictly greater than `self`, as judged by `is_probable_prime` with 32
+    /// rounds.
+    pub fn next_prime(&self) -> Bigint {
+        let two = Bigint::from_u32(2);
+        if *self < two {
+            return two;
+        }
+
+        // continue from the next odd number, stepping over the even ones
+        let mut candidate = self.clone() + Bigint::from_u32(1);
+        if divmod_small(&candidate.digits, 2).1 == 0 {
+            candidate = candidate + Bigint::from_u32(1);
+        }
+
+        while !candidate.is_probable_prime(32) {
+            candidate = candidate + two.clone();
+        }
+
+        return candidate;
+    }
+}
+
+/// Multiplies a magnitude by a small number and adds another one to it, in place. The digits are
+/// least significant first, so a carry grows the vector at the end instead of shifting it all.
+fn mul_add_small_le(digits: &mut Vec<u8>, multiplier: u32, addend: u32) {
+    let mut carrier: u64 = addend as u64;
+
+    for digit in digits.iter_mut() {
+        let current = *digit as u64 * multiplier as u64 + carrier;
+        *digit = (current % 10) as u8;
+        carrier = current / 10;
+    }
+
+    while carrier > 0 {
+        digits.push((carrier % 10) as u8);
+        carrier /= 10;
+    }
+}
+
+/// Turns least significant first digits into a magnitude, dropping the zeros on top.
+fn magnitude_from_le(mut digits: Vec<u8>) -> Vec<u8> {
+    while digits.last() == Some(&0) {
+        digits.pop();
+    }
+    digits.reverse();
+
+    return digits;
+}
+
+/// Converts a decimal magnitude to little-endian 16-bit limbs.
+fn to_binary_limbs(digits: &[u8]) -> Vec<u16> {
+    if digits.len() <= SCHOOLBOOK_CONVERSION_DIGITS {
+        return to_binary_limbs_schoolbook(digits);
+    }
+
+    let mut powers: Vec<Vec<u16>> = vec![vec![10]];
+
+    return to_binary_limbs_recursive(digits, &mut powers);
+}
+
+/// Below this many digits, converting by repeated division is faster than splitting.
+const SCHOOLBOOK_CONVERSION_DIGITS: usize = 512;
+
+fn to_binary_limbs_schoolbook(digits: &[u8]) -> Vec<u16> {
+    let mut limbs: Vec<u16> = Vec::new();
+    let mut rest: Vec<u8> = digits.to_vec();
+    strip_leading_zeros(&mut rest);
+
+    while !rest.is_empty() {
+        let (quotient, remainder) = divmod_small(&rest, 1 << 16);
+        limbs.push(remainder as u16);
+        rest = quotient;
+    }
+
+    return limbs;
+}
+
+/// Divide and conquer: `high * 10^k + low`, where `k` is the largest power of two below the
+/// number of digits. `powers[i]` caches `10^(2^i)` in binary, so each of them is squared once.
+fn to_binary_limbs_recursive(digits: &[u8], powers: &mut Vec<Vec<u16>>) -> Vec<u16> {
+    if digits.len() <= SCHOOLBOOK_CONVERSION_DIGITS {
+        return to_binary_limbs_schoolbook(digits);
+    }
+
+    let mut level = 0;
+    while (2usize << level) < digits.len() {
+        level += 1;
+    }
+
+    while powers.len() <= level {
+        let last = powers.last().unwrap().clone();
+        powers.push(mul_limbs(&last, &last));
+    }
+
+    let (high, low) = digits.split_at(digits.len() - (1 << level));
+    let high = to_binary_limbs_recursive(high, powers);
+    let low = to_binary_limbs_recursive(low, powers);
+
+    return add_limbs(&mul_limbs(&high, &powers[level]), &low);
+}
+
+fn trim_limbs(limbs: &mut Vec<u16>) {
+    while limbs.last() == Some(&0) {
+        limbs.pop();
+    }
+}
+
+fn add_limbs(left: &[u16], right: &[u16]) -> Vec<u16> {
+    let mut res: Vec<u16> = Vec::with_capacity(std::cmp::max(left.len(), right.len()) + 1);
+    let mut carrier: u32 = 0;
+
+    for i in 0..std::cmp::max(left.len(), right.len()) {
+        let sum = *left.get(i).unwrap_or(&0) as u32 + *right.get(i).unwrap_or(&0) as u32 + carrier;
+        res.push(sum as u16);
+        carrier = sum >> 16;
+    }
+
+    if carrier != 0 {
+        res.push(carrier as u16);
+    }
+
+    trim_limbs(&mut res);
+
+    return res;
+}
+
+/// Expects `larger >= smaller`.
+fn subtract_limbs(larger: &[u16], smaller: &[u16]) -> Vec<u16> {
+    let mut res: Vec<u16> = Vec::with_capacity(larger.len());
+    let mut borrow: i32 = 0;
+
+    for (i, &limb) in larger.iter().enumerate() {
+        let mut difference = limb as i32 - *smaller.get(i).unwrap_or(&0) as i32 - borrow;
+        borrow = (difference < 0) as i32;
+        if difference < 0 {
+            difference += 1 << 16;
+        }
+
+        res.push(difference as u16);
+    }
+
+    trim_limbs(&mut res);
+
+    return res;
+}
+
+/// Below this many limbs in the shorter factor, Karatsuba isn't worth it.
+const KARATSUBA_LIMBS: usize = 32;
+
+fn mul_limbs(left: &[u16], right: &[u16]) -> Vec<u16> {
+    if left.is_empty() || right.is_empty() {
+        return Vec::new();
+    }
+
+    if std::cmp::min(left.len(), right.len()) < KARATSUBA_LIMBS {
+        return mul_limbs_schoolbook(left, right);
+    }
+
+    // left = l1 * B^m + l0, right = r1 * B^m + r0, and the middle term is
+    // (l0 + l1)(r0 + r1) - l0 r0 - l1 r1
+    let m = std::cmp::max(left.len(), right.len()) / 2;
+    let (l0, l1) = split_limbs(left, m);
+    let (r0, r1) = split_limbs(right, m);
+
+    let low = mul_limbs(&l0, &r0);
+    let high = mul_limbs(&l1, &r1);
+    let middle = mul_limbs(&add_limbs(&l0, &l1), &add_limbs(&r0, &r1));
+    let middle = subtract_limbs(&subtract_limbs(&middle, &low), &high);
+
+    let mut res = low;
+    res = add_limbs(&res, &shift_limbs(&middle, m));
+    res = add_limbs(&res, &shift_limbs(&high, 2 * m));
+
+    return res;
+}
+
+fn split_limbs(limbs: &[u16], at: usize) -> (Vec<u16>, Vec<u16>) {
+    let at = std::cmp::min(at, limbs.len());
+    let mut low = limbs[..at].to_vec();
+    trim_limbs(&mut low);
+
+    return (low, limbs[at..].to_vec());
+}
+
+fn shift_limbs(limbs: &[u16], by: usize) -> Vec<u16> {
+    if limbs.is_empty() {
+        return Vec::new();
+    }
+
+    let mut res = vec![0; by];
+    res.extend_from_slice(limbs);
+
+    return res;
+}
+
+fn mul_limbs_schoolbook(left: &[u16], right: &[u16]) -> Vec<u16> {
+    let mut res: Vec<u16> = vec![0; left.len() + right.len()];
+
+    for (i, &l) in left.iter().enumerate() {
+        let mut carrier: u64 = 0;
+
+        for (j, &r) in right.iter().enumerate() {
+            let current = res[i + j] as u64 + l as u64 * r as u64 + carrier;
+            res[i + j] = current as u16;
+            carrier = current >> 16;
+        }
+
+        let mut k = i + right.len();
+        while carrier != 0 {
+            let current = res[k] as u64 + carrier;
+            res[k] = current as u16;
+            carrier = current >> 16;
+            k += 1;
+        }
+    }
+
+    trim_limbs(&mut res);
+
+    return res;
+}
+
+/// One word of a decimal number in base `10^9`, the numbers below are little-endian vectors of
+/// them.
+const DECIMAL_WORD: u64 = 1_000_000_000;
+
+/// Converts little-endian 16-bit limbs back to a decimal magnitude, by divide and conquer like
+/// `to_binary_limbs` in the other direction.
+fn from_binary_limbs(limbs: &[u16]) -> Vec<u8> {
+    let mut powers: Vec<Vec<u64>> = vec![vec![1 << 16]];
+    let words = decimal_words_recursive(limbs, &mut powers);
+
+    let mut digits: Vec<u8> = Vec::with_capacity(words.len() * 9);
+    for word in words.iter().rev() {
+        let mut word = *word;
+        for slot in (0..9).rev() {
+            digits.push((word / 10u64.pow(slot)) as u8);
+            word %= 10u64.pow(slot);
+        }
+    }
+    strip_leading_zeros(&mut digits);
+
+    return digits;
+}
+
+/// Below this many limbs, converting with Horner's method is faster than splitting.
+const SCHOOLBOOK_CONVERSION_LIMBS: usize = 256;
+
+/// Horner's method on decimal words, taking two limbs per step, so each multiply-add moves 32
+/// bits through 9 digits at once.
+fn decimal_words_schoolbook(limbs: &[u16]) -> Vec<u64> {
+    let mut words: Vec<u64> = Vec::new();
+    let mut pairs: Vec<u64> = limbs.chunks(2)
+        .map(|pair| pair[0] as u64 | (*pair.get(1).unwrap_or(&0) as u64) << 16)
+        .collect();
+    pairs.reverse();
+
+    for value in pairs {
+        // every word is below 2^30, so `word << 32` plus the carry still fits in 64 bits
+        let mut carrier = value;
+        for word in words.iter_mut() {
+            let current = (*word << 32) + carrier;
+            *word = current % DECIMAL_WORD;
+            carrier = current / DECIMAL_WORD;
+        }
+        while carrier > 0 {
+            words.push(carrier % DECIMAL_WORD);
+            carrier /= DECIMAL_WORD;
+        }
+    }
+
+    return words;
+}
+
+/// Divide and conquer: `high * 2^(16k) + low`, where `k` is the largest power of two below the
+/// number of limbs. `powers[i]` caches `2^(16 * 2^i)` in decimal words.
+fn decimal_words_recursive(limbs: &[u16], powers: &mut Vec<Vec<u64>>) -> Vec<u64> {
+    if limbs.len() <= SCHOOLBOOK_CONVERSION_LIMBS {
+        return decimal_words_schoolbook(limbs);
+    }
+
+    let mut level = 0;
+    while (2usize << level) < limbs.len() {
+        level += 1;
+    }
+
+    while powers.len() <= level {
+        let last = powers.last().unwrap().clone();
+        powers.push(mul_decimal_words(&last, &last));
+    }
+
+    let (low, high) = limbs.split_at(1 << level);
+    let high = decimal_words_recursive(high, powers);
+    let low = decimal_words_recursive(low, powers);
+
+    return add_decimal_words(&mul_decimal_words(&high, &powers[level]), &low);
+}
+
+fn trim_decimal_words(words: &mut Vec<u64>) {
+    while words.last() == Some(&0) {
+        words.pop();
+    }
+}
+
+fn add_decimal_words(left: &[u64], right: &[u64]) -> Vec<u64> {
+    let mut res: Vec<u64> = Vec::with_capacity(std::cmp::max(left.len(), right.len()) + 1);
+    let mut carrier: u64 = 0;
+
+    for i in 0..std::cmp::max(left.len(), right.len()) {
+        let sum = *left.get(i).unwrap_or(&0) + *right.get(i).unwrap_or(&0) + carrier;
+        res.push(sum % DECIMAL_WORD);
+        carrier = sum / DECIMAL_WORD;
+    }
+
+    if carrier != 0 {
+        res.push(carrier);
+    }
+
+    trim_decimal_words(&mut res);
+
+    return res;
+}
+
+/// Expects `larger >= smaller`.
+fn subtract_decimal_words(larger: &[u64], smaller: &[u64]) -> Vec<u64> {
+    let mut res: Vec<u64> = Vec::with_capacity(larger.len());
+    let mut borrow: u64 = 0;
+
+    for (i, &word) in larger.iter().enumerate() {
+        let subtrahend = *smaller.get(i).unwrap_or(&0) + borrow;
+        borrow = (word < subtrahend) as u64;
+        res.push(word + borrow * DECIMAL_WORD - subtrahend);
+    }
+
+    trim_decimal_words(&mut res);
+
+    return res;
+}
+
+/// Karatsuba, like `mul_limbs`, in decimal words.
+fn mul_decimal_words(left: &[u64], right: &[u64]) -> Vec<u64> {
+    if left.is_empty() || right.is_empty() {
+        return Vec::new();
+    }
+
+    if std::cmp::min(left.len(), right.len()) < KARATSUBA_LIMBS {
+        return mul_decimal_words_schoolbook(left, right);
+    }
+
+    let m = std::cmp::max(left.len(), right.len()) / 2;
+    let (l0, l1) = split_decimal_words(left, m);
+    let (r0, r1) = split_decimal_words(right, m);
+
+    let low = mul_decimal_words(&l0, &r0);
+    let high = mul_decimal_words(&l1, &r1);
+    let middle = mul_decimal_words(&add_decimal_words(&l0, &l1), &add_decimal_words(&r0, &r1));
+    let middle = subtract_decimal_words(&subtract_decimal_words(&middle, &low), &high);
+
+    let mut res = low;
+    res = add_decimal_words(&res, &shift_decimal_words(&middle, m));
+    res = add_decimal_words(&res, &shift_decimal_words(&high, 2 * m));
+
+    return res;
+}
+
+fn split_decimal_words(words: &[u64], at: usize) -> (Vec<u64>, Vec<u64>) {
+    let at = std::cmp::min(at, words.len());
+    let mut low = words[..at].to_vec();
+    trim_decimal_words(&mut low);
+
+    return (low, words[at..].to_vec());
+}
+
+fn shift_decimal_words(words: &[u64], by: usize) -> Vec<u64> {
+    if words.is_empty() {
+        return Vec::new();
+    }
+
+    let mut res = vec![0; by];
+    res.extend_from_slice(words);
+
+    return res;
+}
+
+fn mul_decimal_words_schoolbook(left: &[u64], right: &[u64]) -> Vec<u64> {
+    let mut res: Vec<u64> = vec![0; left.len() + right.len()];
+
+    for (i, &l) in left.iter().enumerate() {
+        // below 10^9 + (10^9 - 1)^2 + 2 * 10^9, far from overflowing
+        let mut carrier: u64 = 0;
+
+        for (j, &r) in right.iter().enumerate() {
+            let current = res[i + j] + l * r + carrier;
+            res[i + j] = current % DECIMAL_WORD;
+            carrier = current / DECIMAL_WORD;
+        }
+
+        let mut k = i + right.len();
+        while carrier != 0 {
+            let current = res[k] + carrier;
+            res[k] = current % DECIMAL_WORD;
+            carrier = current / DECIMAL_WORD;
+            k += 1;
+        }
+    }
+
+    trim_decimal_words(&mut res);
+
+    return res;
+}
+
+impl Bigint {
+    /// The two's complement form in exactly `len` limbs, which have to be enough to hold the value
+    /// and a sign bit. Every bit above them is equal to the sign.
+    fn to_twos_complement(&self, len: usize) -> Vec<u16> {
+        if !self.is_negative() {
+            let mut limbs = to_binary_limbs(&self.digits);
+            limbs.resize(len, 0);
+            return limbs;
+        }
+
+        // -x is the same as !(x - 1)
+        let mut limbs = to_binary_limbs(&(self.abs() - Bigint::from_u32(1)).digits);
+        limbs.resize(len, 0);
+
+        return limbs.into_iter().map(|limb| !limb).collect();
+    }
+
+    fn from_twos_complement(limbs: Vec<u16>, negative: bool) -> Bigint {
+        if !negative {
+            return Bigint::from_parts(Sign::Plus, from_binary_limbs(&limbs));
+        }
+
+        let inverted: Vec<u16> = limbs.into_iter().map(|limb| !limb).collect();
+        let magnitude = Bigint::from_parts(Sign::Plus, from_binary_limbs(&inverted)) + Bigint::from_u32(1);
+
+        return magnitude.get_inversed();
+    }
+
+    /// The number of limbs needed for both values in two's complement, including a sign bit.
+    fn twos_complement_len(&self, other: &Bigint) -> usize {
+        return (std::cmp::max(self.bits(), other.bits()) / 16 + 1) as usize;
+    }
+
+    fn bitwise<F: Fn(u16, u16) -> u16>(self, other: Bigint, op: F) -> Bigint {
+        let len = self.twos_complement_len(&other);
+        let left = self.to_twos_complement(len);
+        let right = other.to_twos_complement(len);
+
+        // the sign is the value of the infinitely repeated top bit
+        let negative = op(
+            if self.is_negative() { u16::MAX } else { 0 },
+            if other.is_negative() { u16::MAX } else { 0 },
+        ) != 0;
+        let limbs: Vec<u16> = left.into_iter().zip(right).map(|(l, r)| op(l, r)).collect();
+
+        return Bigint::from_twos_complement(limbs, negative);
+    }
+
+    /// The `index`-th bit of the two's complement form, so negative numbers have infinitely many
+    /// set bits on top.
+    pub fn bit(&self, index: u64) -> bool {
+        let bits = self.bits();
+        if index >= bits {
+            return self.is_negative();
+        }
+
+        let limbs = self.to_twos_complement((bits / 16 + 1) as usize);
+
+        return (limbs[(index / 16) as usize] >> (index % 16)) & 1 == 1;
+    }
+
+    /// Sets the `index`-th bit of the two's complement form, which keeps the sign of the number.
+    pub fn set_bit(&mut self, index: u64, value: bool) {
+        let bits = self.bits();
+        // above `bits` every bit equals the sign, so only a real change needs room up to `index`
+        if index >= bits && value == self.is_negative() {
+            return;
+        }
+
+        let len = std::cmp::max(bits / 16 + 1, index / 16 + 2) as usize;
+        let mut limbs = self.to_twos_complement(len);
+        let mask: u16 = 1 << (index % 16);
+
+        if value {
+            limbs[(index / 16) as usize] |= mask;
+        } else {
+            limbs[(index / 16) as usize] &= !mask;
+        }
+
+        *self = Bigint::from_twos_complement(limbs, self.is_negative());
+    }
+
+    /// The number of ones in the absolute value, like Python's `int.bit_count`.
+    pub fn count_ones(&self) -> u64 {
+        return to_binary_limbs(&self.digits).iter().map(|limb| limb.count_ones() as u64).sum();
+    }
+
+    /// The number of zeros below the lowest set bit, `None` for zero. It is the same for the
+    /// absolute value and for the two's complement form.
+    pub fn trailing_zeros(&self) -> Option<u64> {
+        let limbs = to_binary_limbs(&self.digits);
+        let index = limbs.iter().position(|&limb| limb != 0)?;
+
+        return Some(index as u64 * 16 + limbs[index].trailing_zeros() as u64);
+    }
+}
+
+impl BitAnd for Bigint {
+    type Output = Bigint;
+
+    fn bitand(self, other: Self) -> Self {
+        return self.bitwise(other, |l, r| l & r);
+    }
+}
+
+impl BitOr for Bigint {
+    type Output = Bigint;
+
+    fn bitor(self, other: Self) -> Self {
+        return self.bitwise(other, |l, r| l | r);
+    }
+}
+
+impl BitXor for Bigint {
+    type Output = Bigint;
+
+    fn bitxor(self, other: Self) -> Self {
+        return self.bitwise(other, |l, r| l ^ r);
+    }
+}
+
+/// `!x` is `-x - 1`, as in two's complement.
+impl Not for Bigint {
+    type Output = Bigint;
+
+    fn not(self) -> Self {
+        return self.get_inversed() - Bigint::from_u32(1);
+    }
+}
+
+/// Multiplies by `2^shift`.
+impl Shl<u32> for Bigint {
+    type Output = Bigint;
+
+    #[allow(clippy::suspicious_arithmetic_impl)]
+    fn shl(self, shift: u32) -> Self {
+        return self * Bigint::from_u32(2).pow(shift);
+    }
+}
+
+/// Divides by `2^shift`, rounding down -- an arithmetic shift, so negative numbers stay negative.
+impl Shr<u32> for Bigint {
+    type Output = Bigint;
+
+    fn shr(self, shift: u32) -> Self {
+        let divisor = Bigint::from_u32(2).pow(shift);
+
+        if !self.is_negative() {
+            return self / divisor;
+        }
+
+        // floor(-x / d) == -((x - 1) / d) - 1 for a positive x
+        let magnitude = (self.abs() - Bigint::from_u32(1)) / divisor;
+
+        return magnitude.get_inversed() - Bigint::from_u32(1);
+    }
+}
+
+fn limbs_to_bytes_le(limbs: &[u16]) -> Vec<u8> {
+    return limbs.iter().flat_map(|limb| limb.to_le_bytes()).collect();
+}
+
+fn bytes_to_limbs_le(bytes: &[u8]) -> Vec<u16> {
+    return bytes.chunks(2)
+        .map(|chunk| u16::from_le_bytes([chunk[0], if chunk.len() == 2 { chunk[1] } else { 0 }]))
+        .collect();
+}
+
+impl Bigint {
+    /// The sign and the little-endian bytes of the absolute value. Zero is `[0]`.
+    pub fn to_bytes_le(&self) -> (Sign, Vec<u8>) {
+        let mut bytes = limbs_to_bytes_le(&to_binary_limbs(&self.digits));
+
+        while bytes.last() == Some(&0) {
+            bytes.pop();
+        }
+
+        if bytes.is_empty() {
+            bytes.push(0);
+        }
+
+        return (self.sign, bytes);
+    }
+
+    /// The sign and the big-endian bytes of the absolute value. Zero is `[0]`.
+    pub fn to_bytes_be(&self) -> (Sign, Vec<u8>) {
+        let (sign, mut bytes) = self.to_bytes_le();
+        bytes.reverse();
+
+        return (sign, bytes);
+    }
+
+    /// The inverse of `to_bytes_le`. The sign is ignored if the bytes are all zeros.
+    pub fn from_bytes_le(sign: Sign, bytes: &[u8]) -> Bigint {
+        return Bigint::from_parts(sign, from_binary_limbs(&bytes_to_limbs_le(bytes)));
+    }
+
+    /// The inverse of `to_bytes_be`. The sign is ignored if the bytes are all zeros.
+    pub fn from_bytes_be(sign: Sign, bytes: &[u8]) -> Bigint {
+        let mut reversed = bytes.to_vec();
+        reversed.reverse();
+
+        return Bigint::from_bytes_le(sign, &reversed);
+    }
+
+    /// The shortest little-endian two's complement form, the top bit of the last byte is the sign.
+    pub fn to_signed_bytes_le(&self) -> Vec<u8> {
+        let len = (self.bits() / 16 + 1) as usize;
+        let mut bytes = limbs_to_bytes_le(&self.to_twos_complement(len));
+
+        // drop the bytes that only repeat the sign of the one below them
+        while bytes.len() > 1 {
+            let last = bytes[bytes.len() - 1];
+            let below_is_negative = bytes[bytes.len() - 2] & 0x80 != 0;
+
+            if (last == 0 && !below_is_negative) || (last == 0xFF && below_is_negative) {
+                bytes.pop();
+            } else {
+                break;
+            }
+        }
+
+        return bytes;
+    }
+
+    /// The shortest big-endian two's complement form, the top bit of the first byte is the sign.
+    pub fn to_signed_bytes_be(&self) -> Vec<u8> {
+        let mut bytes = self.to_signed_bytes_le();
+        bytes.reverse();
+
+        return bytes;
+    }
+
+    /// The inverse of `to_signed_bytes_le`. No bytes at all mean zero.
+    pub fn from_signed_bytes_le(bytes: &[u8]) -> Bigint {
+        let negative = bytes.last().is_some_and(|last| last & 0x80 != 0);
+        let mut extended = bytes.to_vec();
+
+        // sign-extend to a whole number of limbs
+        if extended.len() % 2 == 1 {
+            extended.push(if negative { 0xFF } else { 0 });
+        }
+
+        return Bigint::from_twos_complement(bytes_to_limbs_le(&extended), negative);
+    }
+
+    /// The inverse of `to_signed_bytes_be`. No bytes at all mean zero.
+    pub fn from_signed_bytes_be(bytes: &[u8]) -> Bigint {
+        let mut reversed = bytes.to_vec();
+        reversed.reverse();
+
+        return Bigint::from_signed_bytes_le(&reversed);
+    }
+
+    /// Zigzag maps the integers to the naturals: 0, -1, 1, -2, 2... become 0, 1, 2, 3, 4...
+    fn zigzag(&self) -> Bigint {
+        let doubled = self.abs() * Bigint::from_u32(2);
+
+        return if self.is_negative() { doubled - Bigint::from_u32(1) } else { doubled };
+    }
+
+    fn unzigzag(self) -> Bigint {
+        let (halved, odd) = divmod_small(&self.digits, 2);
+        let halved = Bigint::from_parts(Sign::Plus, halved);
+
+        return if odd == 1 { (halved + Bigint::from_u32(1)).get_inversed() } else { halved };
+    }
+
+    /// Writes the number in the wire format: the length of the payload as an unsigned LEB128
+    /// varint, followed by the little-endian bytes of the zigzag-encoded value. Zero has an empty
+    /// payload.
+    pub fn encode_to<W: Write>(&self, mut writer: W) -> std::io::Result<()> {
+        let mut payload = self.zigzag().to_bytes_le().1;
+        if payload == [0] {
+            payload.clear();
+        }
+
+        let mut len = payload.len() as u64;
+        loop {
+            let byte = (len & 0x7F) as u8;
+            len >>= 7;
+
+            if len == 0 {
+                writer.write_all(&[byte])?;
+                break;
+            }
+
+            writer.write_all(&[byte | 0x80])?;
+        }
+
+        writer.write_all(&payload)?;
+
+        return Ok(());
+    }
+
+    /// The wire format of `encode_to`, as a vector.
+    pub fn to_wire_bytes(&self) -> Vec<u8> {
+        let mut res: Vec<u8> = Vec::new();
+        self.encode_to(&mut res).unwrap();
+
+        return res;
+    }
+
+    /// Reads a single number in the wire format of `encode_to`. Running out of input anywhere in
+    /// the number is `DecodeError::Truncated`.
+    pub fn decode_from<R: Read>(reader: R) -> Result<Bigint, DecodeError> {
+        return match WireDecoder::new(reader).next() {
+            Some(res) => res,
+            None => Err(DecodeError::Truncated),
+        }
+    }
+}
+
+/// Reads consecutive numbers in the wire format of `Bigint::encode_to` from a stream. The
+/// iteration ends cleanly only if the input ends right between two numbers.
+///
+/// After an error the decoder is fused and yields nothing more: the stream is left somewhere in
+/// the middle of a number, so whatever followed would be read from the wrong place.
+pub struct WireDecoder<R: Read> {
+    reader: R,
+    failed: bool,
+}
+
+impl<R: Read> WireDecoder<R> {
+    pub fn new(reader: R) -> Self {
+        return Self { reader, failed: false };
+    }
+
+    /// Reads a single byte, `Ok(None)` means the end of the input.
+    fn read_byte(&mut self) -> Result<Option<u8>, DecodeError> {
+        let mut byte = [0u8; 1];
+
+        loop {
+            return match self.reader.read(&mut byte) {
+                Ok(0) => Ok(None),
+                Ok(_) => Ok(Some(byte[0])),
+                Err(error) if error.kind() == std::io::ErrorKind::Interrupted => continue,
+                Err(error) => Err(DecodeError::IO(error)),
+            }
+        }
+    }
+
+    fn read_len(&mut self, first: u8) -> Result<u64, DecodeError> {
+        let mut len: u64 = 0;
+        let mut shift: u32 = 0;
+        let mut byte = first;
+
+        loop {
+            if shift >= 64 || (shift == 63 && byte & 0x7F > 1) {
+                return Err(DecodeError::LengthOverflow);
+            }
+
+            len |= ((byte & 0x7F) as u64) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(len);
+            }
+
+            shift += 7;
+            byte = self.read_byte()?.ok_or(DecodeError::Truncated)?;
+        }
+    }
+
+    fn read_number(&mut self) -> Option<Result<Bigint, DecodeError>> {
+        let first = match self.read_byte() {
+            Ok(Some(byte)) => byte,
+            Ok(None) => return None,
+            Err(error) => return Some(Err(error)),
+        };
+
+        let len = match self.read_len(first) {
+            Ok(len) => len,
+            Err(error) => return Some(Err(error)),
+        };
+
+        // read through `take`, so a huge bogus length doesn't allocate up front
+        let mut payload: Vec<u8> = Vec::new();
+        let read = match (&mut self.reader).take(len).read_to_end(&mut payload) {
+            Ok(read) => read,
+            Err(error) => return Some(Err(DecodeError::IO(error))),
+        };
+
+        if (read as u64) < len {
+            return Some(Err(DecodeError::Truncated));
+        }
+
+        return Some(Ok(Bigint::from_bytes_le(Sign::Plus, &payload).unzigzag()));
+    }
+}
+
+impl<R: Read> Iterator for WireDecoder<R> {
+    type Item = Result<Bigint, DecodeError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+
+        let res = self.read_number();
+        self.failed = matches!(res, Some(Err(_)));
+
+        return res;
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RoundingMode {
+    /// To the nearest, ties go to the even neighbour.
+    HalfEven,
+    /// To the nearest, ties go away from zero.
+    HalfUp,
+    /// Towards negative infinity.
+    Floor,
+    /// Towards positive infinity.
+    Ceiling,
+    /// Towards zero.
+    Truncate,
+}
+
+/// Divides `numer` by a positive `denom` and rounds the result to an integer.
+fn round_div(numer: &Bigint, denom: &Bigint, mode: RoundingMode) -> Bigint {
+    let (quotient, remainder) = numer.div_rem(denom).unwrap();
+
+    if remainder.is_zero() {
+        return quotient;
+    }
+
+    let away_from_zero = match mode {
+        RoundingMode::Truncate => false,
+        RoundingMode::Floor => numer.is_negative(),
+        RoundingMode::Ceiling => numer.is_positive(),
+        RoundingMode::HalfUp | RoundingMode::HalfEven => {
+            match (remainder.abs() * Bigint::from_u32(2)).cmp(denom) {
+                Ordering::Less => false,
+                Ordering::Greater => true,
+                Ordering::Equal => mode == RoundingMode::HalfUp || divmod_small(&quotient.digits, 2).1 == 1,
+            }
+        },
+    };
+
+    if !away_from_zero {
+        return quotient;
+    }
+
+    return if numer.is_negative() {
+        quotient - Bigint::from_u32(1)
+    } else {
+        quotient + Bigint::from_u32(1)
+    }
+}
+
+/// Writes `value / 10^scale` with exactly `scale` digits after the decimal point.
+fn format_scaled(value: &Bigint, scale: usize) -> String {
+    let mut digits: String = value.digits.iter().map(|&digit| (b'0' + digit) as char).collect();
+    let mut res = String::new();
+
+    if value.is_negative() {
+        res.push('-');
+    }
+
+    if scale == 0 {
+        res.push_str(if digits.is_empty() { "0" } else { &digits });
+        return res;
+    }
+
+    // pad with zeros, so there is at least one digit before the point
+    if digits.len() <= scale {
+        digits = "0".repeat(scale + 1 - digits.len()) + &digits;
+    }
+
+    let (integer, fraction) = digits.split_at(digits.len() - scale);
+    res.push_str(integer);
+    res.push('.');
+    res.push_str(fraction);
+
+    return res;
+}
+
+/// Parses an unsigned decimal like "12.375", ".5" or "7", returning all the digits and the number
+/// of them after the point.
+fn parse_unsigned_decimal(s: &str) -> Result<(Vec<u8>, usize), ParseError> {
+    let (integer, fraction) = match s.find('.') {
+        Some(index) => (&s[..index], &s[index + 1..]),
+        None => (s, ""),
+    };
+
+    if integer.is_empty() && fraction.is_empty() {
+        return Err(ParseError);
+    }
+
+    let mut digits: Vec<u8> = Vec::new();
+    for char in integer.chars().chain(fraction.chars()) {
+        if !char.is_ascii_digit() {
+            return Err(ParseError);
+        }
+
+        digits.push(char.to_digit(10).unwrap() as u8);
+    }
+
+    return Ok((digits, fraction.len()));
+}
+
+/// Splits an optional leading sign off a number.
+fn split_sign(s: &str) -> (Sign, &str) {
+    if let Some(rest) = s.strip_prefix('-') {
+        return (Sign::Minus, rest);
+    }
+
+    return (Sign::Plus, s.strip_prefix('+').unwrap_or(s));
+}
+
+/// An exact fraction. It is always reduced and the denominator is always positive, so equal
+/// numbers have equal representations.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct BigRational {
+    numer: Bigint,
+    denom: Bigint,
+}
+
+impl BigRational {
+    pub fn new(numer: Bigint, denom: Bigint) -> Result<Self, ArithmeticError> {
+        if denom.is_zero() {
+            return Err(ArithmeticError::DivideByZero);
+        }
+
+        return Ok(Self::reduced(numer, denom));
+    }
+
+    pub fn from_integer(value: Bigint) -> Self {
+        return Self { numer: value, denom: Bigint::from_u32(1) };
+    }
+
+    /// Expects a non-zero `denom`.
+    fn reduced(numer: Bigint, denom: Bigint) -> Self {
+        let g = numer.gcd(&denom);
+        let mut numer = numer / g.clone();
+        let mut denom = denom / g;
+
+        if denom.is_negative() {
+            numer = numer.get_inversed();
+            denom = denom.get_inversed();
+        }
+
+        return Self { numer, denom };
+    }
+
+    pub fn numer(&self) -> &Bigint {
+        return &self.numer;
+    }
+
+    pub fn denom(&self) -> &Bigint {
+        return &self.denom;
+    }
+
+    pub fn is_integer(&self) -> bool {
+        return self.denom.is_one();
+    }
+
+    pub fn recip(&self) -> Result<Self, ArithmeticError> {
+        return BigRational::new(self.denom.clone(), self.numer.clone());
+    }
+
+    /// Rounds to an integer with the given rounding mode.
+    pub fn round(&self, mode: RoundingMode) -> Bigint {
+        return round_div(&self.numer, &self.denom, mode);
+    }
+
+    /// Writes the number as a decimal with exactly `places` digits after the point, rounding
+    /// the rest away with `mode`.
+    pub fn to_decimal_string(&self, places: usize, mode: RoundingMode) -> String {
+        let scaled = round_div(&(self.numer.clone() * Bigint::power_of_ten(places)), &self.denom, mode);
+
+        return format_scaled(&scaled, places);
+    }
+}
+
+/// Parses both fractions like "-3/4" and decimals like "-12.375".
+impl FromStr for BigRational {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some((numer, denom)) = s.split_once('/') {
+            if split_sign(numer).1.is_empty() || split_sign(denom).1.is_empty() {
+                return Err(ParseError);
+            }
+
+            let numer = Bigint::from_str(numer)?;
+            let denom = Bigint::from_str(denom)?;
+
+            return BigRational::new(numer, denom).map_err(|_| ParseError);
+        }
+
+        let (sign, unsigned) = split_sign(s);
+        let (digits, scale) = parse_unsigned_decimal(unsigned)?;
+
+        return Ok(BigRational::reduced(Bigint::from_parts(sign, digits), Bigint::power_of_ten(scale)));
+    }
+}
+
+/// Writes "numer/denom", or just the numerator for integers.
+impl fmt::Display for BigRational {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_integer() {
+            return write!(f, "{}", self.numer);
+        }
+
+        return write!(f, "{}/{}", self.numer, self.denom);
+    }
+}
+
+impl PartialOrd for BigRational {
+    fn partial_cmp(&self, other: &BigRational) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for BigRational {
+    fn cmp(&self, other: &BigRational) -> Ordering {
+        // the denominators are positive, so cross-multiplying keeps the order
+        let left = self.numer.clone() * other.denom.clone();
+        let right = other.numer.clone() * self.denom.clone();
+
+        return left.cmp(&right);
+    }
+}
+
+impl Add for BigRational {
+    type Output = BigRational;
+
+    fn add(self, other: Self) -> Self {
+        let numer = self.numer * other.denom.clone() + other.numer * self.denom.clone();
+
+        return BigRational::reduced(numer, self.denom * other.denom);
+    }
+}
+
+impl Sub for BigRational {
+    type Output = BigRational;
+
+    fn sub(self, other: Self) -> Self {
+        return self + (-other);
+    }
+}
+
+impl Mul for BigRational {
+    type Output = BigRational;
+
+    fn mul(self, other: Self) -> Self {
+        return BigRational::reduced(self.numer * other.numer, self.denom * other.denom);
+    }
+}
+
+/// Panics on division by zero.
+impl Div for BigRational {
+    type Output = BigRational;
+
+    #[allow(clippy::suspicious_arithmetic_impl)]
+    fn div(self, other: Self) -> Self {
+        return self * other.recip().expect("attempt to divide by zero");
+    }
+}
+
+/// The remainder of the truncating division, `self - other * trunc(self / other)`. Panics on
+/// division by zero.
+impl Rem for BigRational {
+    type Output = BigRational;
+
+    fn rem(self, other: Self) -> Self {
+        let quotient = (self.clone() / other.clone()).round(RoundingMode::Truncate);
+
+        return self - other * BigRational::from_integer(quotient);
+    }
+}
+
+impl Neg for BigRational {
+    type Output = BigRational;
+
+    fn neg(self) -> Self {
+        return Self { numer: self.numer.get_inversed(), denom: self.denom };
+    }
+}
+
+/// A decimal number, `unscaled / 10^scale`. The scale is kept as given, so "123.4500" stays with
+/// four digits after the point; comparisons go by value, so 1.0 and 1.00 are equal.
+#[derive(Debug, Clone)]
+pub struct BigDecimal {
+    unscaled: Bigint,
+    scale: u32,
+}
+
+impl BigDecimal {
+    pub fn new(unscaled: Bigint, scale: u32) -> Self {
+        return Self { unscaled, scale };
+    }
+
+    pub fn from_integer(value: Bigint) -> Self {
+        return Self { unscaled: value, scale: 0 };
+    }
+
+    pub fn unscaled(&self) -> &Bigint {
+        return &self.unscaled;
+    }
+
+    pub fn scale(&self) -> u32 {
+        return self.scale;
+    }
+
+    /// The same number with `scale` digits after the point. Growing the scale is exact, shrinking
+    /// it rounds with `mode`.
+    pub fn rescale(&self, scale: u32, mode: RoundingMode) -> BigDecimal {
+        if scale >= self.scale {
+            let factor = Bigint::power_of_ten((scale - self.scale) as usize);
+            return BigDecimal::new(self.unscaled.clone() * factor, scale);
+        }
+
+        let divisor = Bigint::power_of_ten((self.scale - scale) as usize);
+
+        return BigDecimal::new(round_div(&self.unscaled, &divisor, mode), scale);
+    }
+
+    /// Both numbers brought exactly to the bigger of the two scales.
+    fn aligned(self, other: BigDecimal) -> (Bigint, Bigint, u32) {
+        let scale = std::cmp::max(self.scale, other.scale);
+        let left = self.rescale(scale, RoundingMode::Truncate).unscaled;
+        let right = other.rescale(scale, RoundingMode::Truncate).unscaled;
+
+        return (left, right, scale);
+    }
+
+    /// The exact product. Fails with `ArithmeticError::Overflow` if the scales add up to more
+    /// than a `u32`.
+    pub fn checked_mul(&self, other: &BigDecimal) -> Result<BigDecimal, ArithmeticError> {
+        let scale = self.scale.checked_add(other.scale).ok_or(ArithmeticError::Overflow)?;
+
+        return Ok(BigDecimal::new(self.unscaled.clone() * other.unscaled.clone(), scale));
+    }
+
+    /// Divides by `other`, keeping `scale` digits after the point and rounding the rest with
+    /// `mode`. Fails with `ArithmeticError::Overflow` if `scale` and the scale of `other` add up
+    /// to more than a `u32`.
+    pub fn div_to_scale(&self, other: &BigDecimal, scale: u32, mode: RoundingMode) -> Result<BigDecimal, ArithmeticError> {
+        if other.unscaled.is_zero() {
+            return Err(ArithmeticError::DivideByZero);
+        }
+
+        // (a / 10^s) / (b / 10^t) * 10^scale == a * 10^(scale + t) / (b * 10^s)
+        let shift = scale.checked_add(other.scale).ok_or(ArithmeticError::Overflow)?;
+        let mut numer = self.unscaled.clone() * Bigint::power_of_ten(shift as usize);
+        let mut denom = other.unscaled.clone() * Bigint::power_of_ten(self.scale as usize);
+
+        if denom.is_negative() {
+            numer = numer.get_inversed();
+            denom = denom.get_inversed();
+        }
+
+        return Ok(BigDecimal::new(round_div(&numer, &denom, mode), scale));
+    }
+}
+
+impl FromStr for BigDecimal {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (sign, unsigned) = split_sign(s);
+        let (integer, fraction) = unsigned.split_once('.').unwrap_or((unsigned, ""));
+
+        if integer.is_empty() && fraction.is_empty() {
+            return Err(ParseError);
+        }
+
+        // the sign is only allowed in front, `Bigint::from_str` would accept it in the middle
+        if !integer.chars().chain(fraction.chars()).all(|char| char.is_ascii_digit()) {
+            return Err(ParseError);
+        }
+
+        let sign = if sign == Sign::Minus { "-" } else { "" };
+        let unscaled = Bigint::from_str(&format!("{}{}{}", sign, integer, fraction))?;
+
+        return Ok(BigDecimal::new(unscaled, fraction.len() as u32));
+    }
+}
+
+impl fmt::Display for BigDecimal {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        return f.pad(&format_scaled(&self.unscaled, self.scale as usize));
+    }
+}
+
+impl PartialEq for BigDecimal {
+    fn eq(&self, other: &BigDecimal) -> bool {
+        return self.cmp(other) == Ordering::Equal;
+    }
+}
+
+impl Eq for BigDecimal {}
+
+impl PartialOrd for BigDecimal {
+    fn partial_cmp(&self, other: &BigDecimal) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for BigDecimal {
+    fn cmp(&self, other: &BigDecimal) -> Ordering {
+        let (left, right, _) = self.clone().aligned(other.clone());
+
+        return left.cmp(&right);
+    }
+}
+
+impl Add for BigDecimal {
+    type Output = BigDecimal;
+
+    fn add(self, other: Self) -> Self {
+        let (left, right, scale) = self.aligned(other);
+
+        return BigDecimal::new(left + right, scale);
+    }
+}
+
+impl Sub for BigDecimal {
+    type Output = BigDecimal;
+
+    fn sub(self, other: Self) -> Self {
+        let (left, right, scale) = self.aligned(other);
+
+        return BigDecimal::new(left - right, scale);
+    }
+}
+
+/// Panics if the scales add up to more than a `u32`, see `BigDecimal::checked_mul`.
+impl Mul for BigDecimal {
+    type Output = BigDecimal;
+
+    fn mul(self, other: Self) -> Self {
+        return self.checked_mul(&other).expect("attempt to multiply with a scale overflow");
+    }
+}
+
+impl Neg for BigDecimal {
+    type Output = BigDecimal;
+
+    fn neg(self) -> Self {
+        return BigDecimal::new(self.unscaled.get_inversed(), self.scale);
+    }
+}
+
+/// A context for arithmetic modulo a fixed modulus. Values are kept in Montgomery form
+/// `a * R mod N` with `R = 10^k`, so reducing a product only needs cutting off its last `k`
+/// decimal digits instead of a long division. That needs a modulus coprime to 10; even ones and
+/// multiples of 5 fall back to reducing by division.
+#[derive(Debug, Clone)]
+pub struct Montgomery {
+    modulus: Bigint,
+    /// The number of digits of the modulus, `R = 10^k`.
+    k: usize,
+    /// `-N^-1 mod R`, `None` for a modulus that shares a factor with 10. Those have no Montgomery
+    /// form with a decimal `R`, so their values are kept as they are and reduced by division.
+    modulus_neg_inverse: Option<Bigint>,
+    /// `R^2 mod N`, used for converting into Montgomery form. It's 1 without one.
+    r_squared: Bigint,
+}
+
+/// A value modulo the modulus of its `Montgomery` context, kept in Montgomery form.
+#[derive(Debug, Clone)]
+pub struct ModInt<'a> {
+    value: Bigint,
+    context: &'a Montgomery,
+}
+
+impl Montgomery {
+    /// Fails with `ArithmeticError::NonPositiveModulus` for a modulus below 1. Every positive
+    /// modulus works, but only the ones coprime to 10 get the fast Montgomery reduction.
+    pub fn new(modulus: Bigint) -> Result<Self, ArithmeticError> {
+        if !modulus.is_positive() {
+            return Err(ArithmeticError::NonPositiveModulus);
+        }
+
+        let k = modulus.get_size();
+        let r = Bigint::power_of_ten(k);
+        let inverse = match modulus.mod_inverse(&r) {
+            Ok(inverse) => inverse,
+            Err(_) => {
+                return Ok(Self { modulus, k, modulus_neg_inverse: None, r_squared: Bigint::one() });
+            },
+        };
+        let modulus_neg_inverse = Some((r.clone() - inverse).modulo(&r)?);
+        let r_squared = (r.clone() * r).modulo(&modulus)?;
+
+        return Ok(Self { modulus, k, modulus_neg_inverse, r_squared });
+    }
+
+    pub fn modulus(&self) -> &Bigint {
+        return &self.modulus;
+    }
+
+    /// The last `k` digits of a non-negative value, i.e. the value modulo `R`.
+    fn low_digits(&self, value: &Bigint) -> Bigint {
+        let start = value.get_size().saturating_sub(self.k);
+
+        return Bigint::from_parts(Sign::Plus, value.digits[start..].to_vec());
+    }
+
+    /// Montgomery reduction: `value * R^-1 mod N`, for a non-negative `value` below `N * R`.
+    /// Without a Montgomery form it's just `value mod N`.
+    fn reduce(&self, value: Bigint) -> Bigint {
+        let modulus_neg_inverse = match &self.modulus_neg_inverse {
+            Some(modulus_neg_inverse) => modulus_neg_inverse,
+            None => return value.modulo(&self.modulus).unwrap(),
+        };
+
+        let m = self.low_digits(&(self.low_digits(&value) * modulus_neg_inverse.clone()));
+        let sum = value + m * self.modulus.clone();
+
+        // the sum is divisible by R, so dividing is dropping the last `k` digits
+        let end = sum.get_size().saturating_sub(self.k);
+        let res = Bigint::from_parts(Sign::Plus, sum.digits[..end].to_vec());
+
+        return if res >= self.modulus { res - self.modulus.clone() } else { res };
+    }
+
+    pub fn element(&self, value: &Bigint) -> ModInt<'_> {
+        let reduced = value.modulo(&self.modulus).unwrap();
+
+        return ModInt { value: self.reduce(reduced * self.r_squared.clone()), context: self };
+    }
+
+    pub fn zero(&self) -> ModInt<'_> {
+        return ModInt { value: Bigint::new(), context: self };
+    }
+
+    pub fn one(&self) -> ModInt<'_> {
+        return self.element(&Bigint::from_u32(1));
+    }
+}
+
+impl<'a> ModInt<'a> {
+    /// The value in `0..modulus`, out of Montgomery form.
+    pub fn to_bigint(&self) -> Bigint {
+        return self.context.reduce(self.value.clone());
+    }
+
+    /// A negative `exp` raises the inverse, so it fails if there is none.
+    pub fn pow(&self, exp: &Bigint) -> Result<ModInt<'a>, ArithmeticError> {
+        let mut base = if exp.is_negative() { self.inverse()? } else { self.clone() };
+        let mut exp_digits = exp.digits.to_vec();
+        let mut res = self.context.one();
+
+        while !exp_digits.is_empty() {
+            let (halved, bit) = divmod_small(&exp_digits, 2);
+
+            if bit == 1 {
+                res = res * base.clone();
+            }
+
+            exp_digits = halved;
+            if !exp_digits.is_empty() {
+                base = base.clone() * base;
+            }
+        }
+
+        return Ok(res);
+    }
+
+    pub fn inverse(&self) -> Result<ModInt<'a>, ArithmeticError> {
+        let inverse = self.to_bigint().mod_inverse(&self.context.modulus)?;
+
+        return Ok(self.context.element(&inverse));
+    }
+}
+
+/// Values from different contexts can't be mixed, that panics.
+fn check_same_context(left: &ModInt, right: &ModInt) {
+    if left.context.modulus != right.context.modulus {
+        panic!("ModInt values with different moduli");
+    }
+}
+
+impl<'a> PartialEq for ModInt<'a> {
+    fn eq(&self, other: &ModInt<'a>) -> bool {
+        return self.context.modulus == other.context.modulus && self.value == other.value;
+    }
+}
+
+impl<'a> Eq for ModInt<'a> {}
+
+impl<'a> Add for ModInt<'a> {
+    type Output = ModInt<'a>;
+
+    fn add(self, other: Self) -> Self {
+        check_same_context(&self, &other);
+        let sum = self.value + other.value;
+        let value = if sum >= self.context.modulus { sum - self.context.modulus.clone() } else { sum };
+
+        return ModInt { value, context: self.context };
+    }
+}
+
+impl<'a> Sub for ModInt<'a> {
+    type Output = ModInt<'a>;
+
+    fn sub(self, other: Self) -> Self {
+        check_same_context(&self, &other);
+        let difference = self.value - other.value;
+        let value = if difference.is_negative() { difference + self.context.modulus.clone() } else { difference };
+
+        return ModInt { value, context: self.context };
+    }
+}
+
+impl<'a> Mul for ModInt<'a> {
+    type Output = ModInt<'a>;
+
+    fn mul(self, other: Self) -> Self {
+        check_same_context(&self, &other);
+
+        return ModInt { value: self.context.reduce(self.value * other.value), context: self.context };
+    }
+}
+
+impl<'a> Neg for ModInt<'a> {
+    type Output = ModInt<'a>;
+
+    fn neg(self) -> Self {
+        return self.context.zero() - self;
+    }
+}
+
+impl Bigint {
+    /// Parses a number in the given radix, from 2 to 36, with an optional sign in front. Letters
+    /// are accepted in both cases.
+    pub fn from_str_radix(s: &str, radix: u32) -> Result<Bigint, ParseError> {
+        if !(2..=36).contains(&radix) {
+            return Err(ParseError);
+        }
+
+        let (sign, unsigned) = split_sign(s);
+        if unsigned.is_empty() {
+            return Err(ParseError);
+        }
+
+        let mut digits: Vec<u8> = Vec::new();
+        for char in unsigned.chars() {
+            let digit = char.to_digit(radix).ok_or(ParseError)?;
+            mul_add_small_le(&mut digits, radix, digit);
+        }
+
+        return Ok(Bigint::from_parts(sign, magnitude_from_le(digits)));
+    }
+}
+
+/// The product of all the numbers in `low..=high`, splitting the range in halves, so that the
+/// multiplied numbers stay similar in size.
+fn range_product(low: u64, high: u64) -> Bigint {
+    if low > high {
+        return Bigint::one();
+    }
+
+    if high - low < 8 {
+        let mut res = Bigint::one();
+        for i in low..=high {
+            res = res * Bigint::from_str(&i.to_string()).unwrap();
+        }
+
+        return res;
+    }
+
+    let middle = low + (high - low) / 2;
+
+    return range_product(low, middle) * range_product(middle + 1, high);
+}
+
+impl Bigint {
+    /// `n!`, by binary splitting of the product.
+    pub fn factorial(n: u32) -> Bigint {
+        return range_product(2, n as u64);
+    }
+
+    /// The number of ways to choose `k` out of `n`, 0 if `k > n`.
+    pub fn binomial(n: u32, k: u32) -> Bigint {
+        if k > n {
+            return Bigint::zero();
+        }
+
+        let k = std::cmp::min(k, n - k);
+
+        // n! / (k! (n - k)!) == (n - k + 1) * ... * n / k!
+        return range_product((n - k) as u64 + 1, n as u64) / Bigint::factorial(k);
+    }
+
+    /// `(F(n), F(n + 1))` by fast doubling:
+    /// F(2m) = F(m) * (2F(m + 1) - F(m)) and F(2m + 1) = F(m)^2 + F(m + 1)^2.
+    fn fibonacci_pair(n: u64) -> (Bigint, Bigint) {
+        if n == 0 {
+            return (Bigint::zero(), Bigint::one());
+        }
+
+        let (a, b) = Bigint::fibonacci_pair(n / 2);
+        let even = a.clone() * (b.clone() * Bigint::from_u32(2) - a.clone());
+        let odd = a.clone() * a + b.clone() * b;
+
+        return if n.is_multiple_of(2) { (even, odd) } else { (odd.clone(), even + odd) };
+    }
+
+    /// The `n`-th Fibonacci number, with `F(0) = 0` and `F(1) = 1`.
+    pub fn fibonacci(n: u64) -> Bigint {
+        return Bigint::fibonacci_pair(n).0;
+    }
+
+    /// The `n`-th Lucas number, with `L(0) = 2` and `L(1) = 1`.
+    pub fn lucas(n: u64) -> Bigint {
+        // L(n) == F(n - 1) + F(n + 1) == 2F(n + 1) - F(n)
+        let (current, next) = Bigint::fibonacci_pair(n);
+
+        return next * Bigint::from_u32(2) - current;
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CalcErrorKind {
+    UnexpectedChar(char),
+    UnexpectedEnd,
+    UnexpectedToken(String),
+    InvalidNumber(String),
+    UnknownVariable(String),
+    UnknownFunction(String),
+    WrongArgumentCount { name: String, expected: usize, found: usize },
+    ExponentTooLarge,
+    NegativeExponent,
+    /// The result would have more than `CALC_MAX_DIGITS` digits.
+    ResultTooLarge,
+    Arithmetic(ArithmeticError),
+}
+
+/// An error from parsing or evaluating a calculator line. `position` is the index of the
+/// character in the line, where the offending token starts.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CalcError {
+    pub position: usize,
+    pub kind: CalcErrorKind,
+}
+
+impl CalcError {
+    fn new(position: usize, kind: CalcErrorKind) -> Self {
+        return Self { position, kind };
+    }
+}
+
+impl fmt::Display for CalcError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "column {}: ", self.position + 1)?;
+
+        return match &self.kind {
+            CalcErrorKind::UnexpectedChar(char) => write!(f, "unexpected character '{}'", char),
+            CalcErrorKind::UnexpectedEnd => write!(f, "unexpected end of input"),
+            CalcErrorKind::UnexpectedToken(token) => write!(f, "unexpected '{}'", token),
+            CalcErrorKind::InvalidNumber(number) => write!(f, "invalid number '{}'", number),
+            CalcErrorKind::UnknownVariable(name) => write!(f, "unknown variable '{}'", name),
+            CalcErrorKind::UnknownFunction(name) => write!(f, "unknown function '{}'", name),
+            CalcErrorKind::WrongArgumentCount { name, expected, found } => {
+                write!(f, "'{}' takes {} argument(s), {} given", name, expected, found)
+            },
+            CalcErrorKind::ExponentTooLarge => write!(f, "exponent too large"),
+            CalcErrorKind::NegativeExponent => write!(f, "negative exponent"),
+            CalcErrorKind::ResultTooLarge => write!(f, "result too large"),
+            CalcErrorKind::Arithmetic(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Token {
+    Number(Bigint),
+    Ident(String),
+    Op(char),
+    LeftParen,
+    RightParen,
+    Comma,
+    Assign,
+}
+
+fn describe_token(token: &Option<Token>) -> CalcErrorKind {
+    return match token {
+        None => CalcErrorKind::UnexpectedEnd,
+        Some(Token::Number(number)) => CalcErrorKind::UnexpectedToken(number.to_string()),
+        Some(Token::Ident(name)) => CalcErrorKind::UnexpectedToken(name.clone()),
+        Some(Token::Op(op)) => CalcErrorKind::UnexpectedToken(op.to_string()),
+        Some(Token::LeftParen) => CalcErrorKind::UnexpectedToken("(".to_string()),
+        Some(Token::RightParen) => CalcErrorKind::UnexpectedToken(")".to_string()),
+        Some(Token::Comma) => CalcErrorKind::UnexpectedToken(",".to_string()),
+        Some(Token::Assign) => CalcErrorKind::UnexpectedToken("=".to_string()),
+    }
+}
+
+/// Splits a line into tokens with the indices of their first characters. Numbers are decimal, or
+/// hexadecimal, octal and binary with a `0x`, `0o` or `0b` prefix.
+fn tokenize(line: &str) -> Result<Vec<(Token, usize)>, CalcError> {
+    let mut tokens: Vec<(Token, usize)> = Vec::new();
+    let chars: Vec<(usize, char)> = line.char_indices().collect();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let (offset, char) = chars[i];
+        let position = i;
+
+        if char.is_whitespace() {
+            i += 1;
+            continue;
+        }
+
+        if char.is_ascii_alphanumeric() || char == '_' {
+            // a whole word -- a number with its prefix, or a name
+            let start = i;
+            while i < chars.len() && (chars[i].1.is_ascii_alphanumeric() || chars[i].1 == '_') {
+                i += 1;
+            }
+
+            let end = if i < chars.len() { chars[i].0 } else { line.len() };
+            let word = &line[offset..end];
+
+            if chars[start].1.is_ascii_digit() {
+                tokens.push((Token::Number(parse_literal(word, position)?), position));
+            } else {
+                tokens.push((Token::Ident(word.to_string()), position));
+            }
+
+            continue;
+        }
+
+        let token = match char {
+            '+' | '-' | '*' | '/' | '%' | '^' => Token::Op(char),
+            '(' => Token::LeftParen,
+            ')' => Token::RightParen,
+            ',' => Token::Comma,
+            '=' => Token::Assign,
+            _ => return Err(CalcError::new(position, CalcErrorKind::UnexpectedChar(char))),
+        };
+
+        tokens.push((token, position));
+        i += 1;
+    }
+
+    return Ok(tokens);
+}
+
+fn parse_literal(word: &str, position: usize) -> Result<Bigint, CalcError> {
+    let lowered = word.to_lowercase();
+    let (digits, radix) = if let Some(rest) = lowered.strip_prefix("0x") {
+        (rest, 16)
+    } else if let Some(rest) = lowered.strip_prefix("0o") {
+        (rest, 8)
+    } else if let Some(rest) = lowered.strip_prefix("0b") {
+        (rest, 2)
+    } else {
+        (lowered.as_str(), 10)
+    };
+
+    // `from_str_radix` takes a sign, but the sign of a literal is a separate token
+    if digits.starts_with('+') || digits.starts_with('-') {
+        return Err(CalcError::new(position, CalcErrorKind::InvalidNumber(word.to_string())));
+    }
+
+    return Bigint::from_str_radix(digits, radix)
+        .map_err(|_| CalcError::new(position, CalcErrorKind::InvalidNumber(word.to_string())));
+}
+
+/// A parsed expression. Every node keeps the position of its token, so evaluation errors can point
+/// at the operator or name that caused them.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Expr {
+    Number(Bigint),
+    Variable(String, usize),
+    Negate(Box<Expr>),
+    Binary(char, Box<Expr>, Box<Expr>, usize),
+    Call(String, Vec<Expr>, usize),
+}
+
+/// A single line of input: either an expression, or an assignment of one to a variable.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Statement {
+    Expr(Expr),
+    Assign(String, Expr),
+}
+
+struct Parser {
+    tokens: Vec<(Token, usize)>,
+    index: usize,
+    end: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<Token> {
+        return self.tokens.get(self.index).map(|(token, _)| token.clone());
+    }
+
+    fn position(&self) -> usize {
+        return self.tokens.get(self.index).map_or(self.end, |&(_, position)| position);
+    }
+
+    fn unexpected(&self) -> CalcError {
+        return CalcError::new(self.position(), describe_token(&self.peek()));
+    }
+
+    fn expect(&mut self, expected: Token) -> Result<(), CalcError> {
+        if self.peek() != Some(expected) {
+            return Err(self.unexpected());
+        }
+
+        self.index += 1;
+        return Ok(());
+    }
+
+    fn statement(&mut self) -> Result<Statement, CalcError> {
+        let (first, second) = (self.tokens.first(), self.tokens.get(1));
+        if let (Some((Token::Ident(name), _)), Some((Token::Assign, _))) = (first, second) {
+            let name = name.clone();
+            self.index = 2;
+            return Ok(Statement::Assign(name, self.expression()?));
+        }
+
+        return Ok(Statement::Expr(self.expression()?));
+    }
+
+    /// expression := term (('+' | '-') term)*
+    fn expression(&mut self) -> Result<Expr, CalcError> {
+        let mut left = self.term()?;
+
+        while let Some(Token::Op(op)) = self.peek() {
+            if op != '+' && op != '-' {
+                break;
+            }
+
+            let position = self.position();
+            self.index += 1;
+            left = Expr::Binary(op, Box::new(left), Box::new(self.term()?), position);
+        }
+
+        return Ok(left);
+    }
+
+    /// term := unary (('*' | '/' | '%') unary)*
+    fn term(&mut self) -> Result<Expr, CalcError> {
+        let mut left = self.unary()?;
+
+        while let Some(Token::Op(op)) = self.peek() {
+            if op != '*' && op != '/' && op != '%' {
+                break;
+            }
+
+            let position = self.position();
+            self.index += 1;
+            left = Expr::Binary(op, Box::new(left), Box::new(self.unary()?), position);
+        }
+
+        return Ok(left);
+    }
+
+    /// unary := ('-' | '+') unary | power
+    fn unary(&mut self) -> Result<Expr, CalcError> {
+        return match self.peek() {
+            Some(Token::Op('-')) => {
+                self.index += 1;
+                Ok(Expr::Negate(Box::new(self.unary()?)))
+            },
+            Some(Token::Op('+')) => {
+                self.index += 1;
+                self.unary()
+            },
+            _ => self.power(),
+        }
+    }
+
+    /// power := primary ('^' unary)?, so `^` is right-associative and `-2^2` is `-(2^2)`
+    fn power(&mut self) -> Result<Expr, CalcError> {
+        let base = self.primary()?;
+
+        if self.peek() == Some(Token::Op('^')) {
+            let position = self.position();
+            self.index += 1;
+            return Ok(Expr::Binary('^', Box::new(base), Box::new(self.unary()?), position));
+        }
+
+        return Ok(base);
+    }
+
+    /// primary := number | name | name '(' arguments ')' | '(' expression ')'
+    fn primary(&mut self) -> Result<Expr, CalcError> {
+        let position = self.position();
+
+        return match self.peek() {
+            Some(Token::Number(number)) => {
+                self.index += 1;
+                Ok(Expr::Number(number))
+            },
+            Some(Token::Ident(name)) => {
+                self.index += 1;
+                if self.peek() != Some(Token::LeftParen) {
+                    return Ok(Expr::Variable(name, position));
+                }
+
+                self.index += 1;
+                let mut arguments = vec![];
+                if self.peek() != Some(Token::RightParen) {
+                    arguments.push(self.expression()?);
+                    while self.peek() == Some(Token::Comma) {
+                        self.index += 1;
+                        arguments.push(self.expression()?);
+                    }
+                }
+
+                self.expect(Token::RightParen)?;
+                Ok(Expr::Call(name, arguments, position))
+            },
+            Some(Token::LeftParen) => {
+                self.index += 1;
+                let inner = self.expression()?;
+                self.expect(Token::RightParen)?;
+                Ok(inner)
+            },
+            _ => Err(self.unexpected()),
+        }
+    }
+}
+
+/// Parses a single calculator line. An empty line gives `None`.
+pub fn parse_statement(line: &str) -> Result<Option<Statement>, CalcError> {
+    let tokens = tokenize(line)?;
+    if tokens.is_empty() {
+        return Ok(None);
+    }
+
+    let mut parser = Parser { tokens, index: 0, end: line.trim_end().chars().count() };
+    let statement = parser.statement()?;
+
+    if parser.index != parser.tokens.len() {
+        return Err(parser.unexpected());
+    }
+
+    return Ok(Some(statement));
+}
+
+/// Results with more digits than this are refused, computing them would keep the calculator
+/// busy for minutes.
+const CALC_MAX_DIGITS: f64 = 100_000.0;
+
+/// The decimal logarithm of the absolute value, from its leading digits. Expects a non-zero value.
+fn approx_log10(value: &Bigint) -> f64 {
+    let leading = std::cmp::min(value.get_size(), 15);
+    let mantissa = value.digits[..leading].iter()
+        .fold(0.0, |acc, &digit| acc * 10.0 + digit as f64);
+
+    return (value.get_size() - leading) as f64 + mantissa.log10();
+}
+
+/// `base` to the power of `exp`, or an error if the result would be too large to compute.
+fn checked_pow(base: &Bigint, exp: u32, position: usize) -> Result<Bigint, CalcError> {
+    // 0, 1 and -1 stay small for any exponent
+    if base.abs() > Bigint::one() && exp as f64 * approx_log10(base) > CALC_MAX_DIGITS {
+        return Err(CalcError::new(position, CalcErrorKind::ResultTooLarge));
+    }
+
+    return Ok(base.pow(exp));
+}
+
+/// `n!`, or an error if it would be too large to compute.
+fn checked_factorial(n: u32, position: usize) -> Result<Bigint, CalcError> {
+    // the number of digits by Stirling's approximation, which is off by far less than one
+    let n_float = n as f64;
+    let digits = n_float * (n_float / std::f64::consts::E).log10()
+        + (2.0 * std::f64::consts::PI * n_float).log10() / 2.0;
+
+    if n > 1 && digits > CALC_MAX_DIGITS {
+        return Err(CalcError::new(position, CalcErrorKind::ResultTooLarge));
+    }
+
+    return Ok(Bigint::factorial(n));
+}
+
+fn to_u32(value: &Bigint, position: usize) -> Result<u32, CalcError> {
+    if value.is_negative() {
+        return Err(CalcError::new(position, CalcErrorKind::NegativeExponent));
+    }
+
+    return value.to_u64()
+        .and_then(|value| u32::try_from(value).ok())
+        .ok_or(CalcError::new(position, CalcErrorKind::ExponentTooLarge));
+}
+
+/// Evaluates calculator lines and keeps the variables assigned by them.
+#[derive(Debug, Default)]
+pub struct Calculator {
+    pub variables: HashMap<String, Bigint>,
+}
+
+impl Calculator {
+    pub fn new() -> Self {
+        return Self { variables: HashMap::new() };
+    }
+
+    /// Parses and evaluates a line. Assignments store the value and return it too; empty lines
+    /// return `None`.
+    pub fn eval_line(&mut self, line: &str) -> Result<Option<Bigint>, CalcError> {
+        return match parse_statement(line)? {
+            None => Ok(None),
+            Some(Statement::Expr(expr)) => Ok(Some(self.eval(&expr)?)),
+            Some(Statement::Assign(name, expr)) => {
+                let value = self.eval(&expr)?;
+                self.variables.insert(name, value.clone());
+                Ok(Some(value))
+            },
+        }
+    }
+
+    pub fn eval(&self, expr: &Expr) -> Result<Bigint, CalcError> {
+        return match expr {
+            Expr::Number(number) => Ok(number.clone()),
+            Expr::Variable(name, position) => self.variables.get(name).cloned()
+                .ok_or(CalcError::new(*position, CalcErrorKind::UnknownVariable(name.clone()))),
+            Expr::Negate(inner) => Ok(self.eval(inner)?.get_inversed()),
+            Expr::Binary(op, left, right, position) => {
+                let left = self.eval(left)?;
+                let right = self.eval(right)?;
+                let arithmetic = |error| CalcError::new(*position, CalcErrorKind::Arithmetic(error));
+
+                match op {
+                    '+' => Ok(left + right),
+                    '-' => Ok(left - right),
+                    '*' => Ok(left * right),
+                    '/' => left.div_rem(&right).map(|(quotient, _)| quotient).map_err(arithmetic),
+                    '%' => left.div_rem(&right).map(|(_, remainder)| remainder).map_err(arithmetic),
+                    _ => checked_pow(&left, to_u32(&right, *position)?, *position),
+                }
+            },
+            Expr::Call(name, arguments, position) => self.call(name, arguments, *position),
+        }
+    }
+
+    fn call(&self, name: &str, arguments: &[Expr], position: usize) -> Result<Bigint, CalcError> {
+        let expected = match name {
+            "gcd" | "pow" => 2,
+            "sqrt" | "fact" => 1,
+            _ => return Err(CalcError::new(position, CalcErrorKind::UnknownFunction(name.to_string()))),
+        };
+
+        if arguments.len() != expected {
+            return Err(CalcError::new(position, CalcErrorKind::WrongArgumentCount {
+                name: name.to_string(),
+                expected,
+                found: arguments.len(),
+            }));
+        }
+
+        let mut values = vec![];
+        for argument in arguments {
+            values.push(self.eval(argument)?);
+        }
+
+        let arithmetic = |error| CalcError::new(position, CalcErrorKind::Arithmetic(error));
+
+        return match name {
+            "gcd" => Ok(values[0].gcd(&values[1])),
+            "pow" => checked_pow(&values[0], to_u32(&values[1], position)?, position),
+            "sqrt" => values[0].sqrt().map_err(arithmetic),
+            _ => checked_factorial(to_u32(&values[0], position)?, position),
+        }
+    }
+}
+
+/// Reads lines from `input` and writes the value of each one, or the error with a caret under
+/// its position, to `output`. Stops at the end of the input or on a line with `quit`.
+pub fn run_repl<R: BufRead, W: Write>(input: R, mut output: W) -> std::io::Result<()> {
+    let mut calculator = Calculator::new();
+
+    write!(output, "> ")?;
+    output.flush()?;
+
+    for line in input.lines() {
+        let line = line?;
+        if line.trim() == "quit" {
+            break;
+        }
+
+        match calculator.eval_line(&line) {
+            Ok(Some(value)) => writeln!(output, "{}", value)?,
+            Ok(None) => {},
+            Err(error) => {
+                writeln!(output, "  {}^", " ".repeat(error.position))?;
+                writeln!(output, "error: {}", error)?;
+            },
+        }
+
+        write!(output, "> ")?;
+        output.flush()?;
+    }
+
+    return Ok(());
+}
+
+/// The size of the chunks for `Bigint::read_from` and `Bigint::write_to`.
+const IO_CHUNK_SIZE: usize = 64 * 1024;
+
+impl Bigint {
+    /// Reads a decimal number from `reader` in chunks, so the text never has to be in memory as a
+    /// whole. Whitespace around the number is skipped, anything else after it is
+    /// `DecodeError::InvalidDigit` with its byte offset. An input without digits is
+    /// `DecodeError::Truncated`.
+    pub fn read_from<R: Read>(mut reader: R) -> Result<Bigint, DecodeError> {
+        let mut chunk = vec![0u8; IO_CHUNK_SIZE];
+        let mut digits: Vec<u8> = Vec::new();
+        let mut sign = Sign::Plus;
+        let mut seen_digit = false;
+        let mut seen_sign = false;
+        let mut finished = false;
+        let mut offset: u64 = 0;
+
+        loop {
+            let read = match reader.read(&mut chunk) {
+                Ok(0) => break,
+                Ok(read) => read,
+                Err(error) if error.kind() == std::io::ErrorKind::Interrupted => continue,
+                Err(error) => return Err(DecodeError::IO(error)),
+            };
+
+            for &byte in &chunk[..read] {
+                let started = seen_digit || seen_sign;
+
+                if byte.is_ascii_whitespace() && (!started || seen_digit) {
+                    finished = started;
+                } else if finished {
+                    return Err(DecodeError::InvalidDigit(offset));
+                } else if byte.is_ascii_digit() {
+                    // leading zeros are never stored
+                    if !digits.is_empty() || byte != b'0' {
+                        digits.push(byte - b'0');
+                    }
+                    seen_digit = true;
+                } else if (byte == b'-' || byte == b'+') && !started {
+                    sign = if byte == b'-' { Sign::Minus } else { Sign::Plus };
+                    seen_sign = true;
+                } else {
+                    return Err(DecodeError::InvalidDigit(offset));
+                }
+
+                offset += 1;
+            }
+        }
+
+        if !seen_digit {
+            return Err(DecodeError::Truncated);
+        }
+
+        return Ok(Bigint::from_parts(sign, digits));
+    }
+
+    /// Writes the decimal form of the number to `writer` in chunks, without building it as a
+    /// whole string first.
+    pub fn write_to<W: Write>(&self, mut writer: W) -> std::io::Result<()> {
+        if self.is_zero() {
+            return writer.write_all(b"0");
+        }
+
+        if self.is_negative() {
+            writer.write_all(b"-")?;
+        }
+
+        let mut chunk: Vec<u8> = Vec::with_capacity(IO_CHUNK_SIZE);
+        for digits in self.digits.chunks(IO_CHUNK_SIZE) {
+            chunk.clear();
+            chunk.extend(digits.iter().map(|&digit| b'0' + digit));
+            writer.write_all(&chunk)?;
+        }
+
+        return Ok(());
+    }
+}
+
+/// A fixed-width integer of `LIMBS` 64-bit words, in two's complement when `SIGNED`. Parsing and
+/// formatting go through `Bigint`; the arithmetic works on the words directly and comes in the
+/// same flavours as on the primitive integers -- `checked_*`, `wrapping_*`, `overflowing_*` and
+/// `saturating_*`. The operators panic on overflow.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FixedInt<const LIMBS: usize, const SIGNED: bool> {
+    /// Little-endian words.
+    limbs: [u64; LIMBS],
+}
+
+pub type U128 = FixedInt<2, false>;
+pub type U256 = FixedInt<4, false>;
+pub type U512 = FixedInt<8, false>;
+pub type I128 = FixedInt<2, true>;
+pub type I256 = FixedInt<4, true>;
+pub type I512 = FixedInt<8, true>;
+
+fn add_words<const LIMBS: usize>(left: &[u64; LIMBS], right: &[u64; LIMBS]) -> ([u64; LIMBS], bool) {
+    let mut res = [0u64; LIMBS];
+    let mut carrier = false;
+
+    for i in 0..LIMBS {
+        let (sum, first) = left[i].overflowing_add(right[i]);
+        let (sum, second) = sum.overflowing_add(carrier as u64);
+        res[i] = sum;
+        carrier = first || second;
+    }
+
+    return (res, carrier);
+}
+
+fn sub_words<const LIMBS: usize>(left: &[u64; LIMBS], right: &[u64; LIMBS]) -> ([u64; LIMBS], bool) {
+    let mut res = [0u64; LIMBS];
+    let mut borrow = false;
+
+    for i in 0..LIMBS {
+        let (difference, first) = left[i].overflowing_sub(right[i]);
+        let (difference, second) = difference.overflowing_sub(borrow as u64);
+        res[i] = difference;
+        borrow = first || second;
+    }
+
+    return (res, borrow);
+}
+
+fn neg_words<const LIMBS: usize>(words: &[u64; LIMBS]) -> [u64; LIMBS] {
+    return sub_words(&[0u64; LIMBS], words).0;
+}
+
+/// The full product, `2 * LIMBS` words.
+fn mul_words<const LIMBS: usize>(left: &[u64; LIMBS], right: &[u64; LIMBS]) -> Vec<u64> {
+    let mut res = vec![0u64; 2 * LIMBS];
+
+    for i in 0..LIMBS {
+        let mut carrier: u128 = 0;
+
+        for j in 0..LIMBS {
+            let current = res[i + j] as u128 + left[i] as u128 * right[j] as u128 + carrier;
+            res[i + j] = current as u64;
+            carrier = current >> 64;
+        }
+
+        res[i + LIMBS] = carrier as u64;
+    }
+
+    return res;
+}
+
+fn cmp_words<const LIMBS: usize>(left: &[u64; LIMBS], right: &[u64; LIMBS]) -> Ordering {
+    return left.iter().rev().cmp(right.iter().rev());
+}
+
+/// Unsigned long division, one bit at a time. Expects a non-zero `divisor`.
+fn divmod_words<const LIMBS: usize>(dividend: &[u64; LIMBS], divisor: &[u64; LIMBS]) -> ([u64; LIMBS], [u64; LIMBS]) {
+    let mut quotient = [0u64; LIMBS];
+    let mut remainder = [0u64; LIMBS];
+
+    for bit in (0..LIMBS * 64).rev() {
+        // remainder = remainder * 2 + the next bit of the dividend
+        let overflow = remainder[LIMBS - 1] >> 63 == 1;
+        for i in (1..LIMBS).rev() {
+            remainder[i] = (remainder[i] << 1) | (remainder[i - 1] >> 63);
+        }
+        remainder[0] = (remainder[0] << 1) | ((dividend[bit / 64] >> (bit % 64)) & 1);
+
+        if overflow || cmp_words(&remainder, divisor) != Ordering::Less {
+            remainder = sub_words(&remainder, divisor).0;
+            quotient[bit / 64] |= 1 << (bit % 64);
+        }
+    }
+
+    return (quotient, remainder);
+}
+
+impl<const LIMBS: usize, const SIGNED: bool> FixedInt<LIMBS, SIGNED> {
+    pub const BITS: u32 = LIMBS as u32 * 64;
+    pub const ZERO: Self = Self { limbs: [0; LIMBS] };
+    pub const MIN: Self = Self::min_value();
+    pub const MAX: Self = Self::max_value();
+
+    const fn min_value() -> Self {
+        let mut limbs = [0u64; LIMBS];
+        if SIGNED {
+            limbs[LIMBS - 1] = 1 << 63;
+        }
+
+        return Self { limbs };
+    }
+
+    const fn max_value() -> Self {
+        let mut limbs = [u64::MAX; LIMBS];
+        if SIGNED {
+            limbs[LIMBS - 1] = u64::MAX >> 1;
+        }
+
+        return Self { limbs };
+    }
+
+    pub fn one() -> Self {
+        let mut limbs = [0u64; LIMBS];
+        limbs[0] = 1;
+
+        return Self { limbs };
+    }
+
+    pub fn is_zero(&self) -> bool {
+        return self.limbs.iter().all(|&limb| limb == 0);
+    }
+
+    pub fn is_negative(&self) -> bool {
+        return SIGNED && self.limbs[LIMBS - 1] >> 63 == 1;
+    }
+
+    /// The absolute value as unsigned words. It fits even for `MIN`.
+    fn magnitude(&self) -> [u64; LIMBS] {
+        return if self.is_negative() { neg_words(&self.limbs) } else { self.limbs };
+    }
+
+    /// A value with the given magnitude and sign, as long as it fits.
+    fn from_magnitude(magnitude: [u64; LIMBS], negative: bool) -> (Self, bool) {
+        let value = Self { limbs: if negative { neg_words(&magnitude) } else { magnitude } };
+
+        let overflow = if !SIGNED {
+            negative && magnitude != [0; LIMBS]
+        } else if negative {
+            cmp_words(&magnitude, &Self::MIN.limbs) == Ordering::Greater
+        } else {
+            magnitude[LIMBS - 1] >> 63 == 1
+        };
+
+        return (value, overflow);
+    }
+
+    pub fn to_bigint(&self) -> Bigint {
+        let limbs: Vec<u16> = self.limbs.iter()
+            .flat_map(|limb| (0..4).map(move |i| (limb >> (16 * i)) as u16))
+            .collect();
+
+        return Bigint::from_twos_complement(limbs, self.is_negative());
+    }
+
+    /// `None` if the value doesn't fit.
+    pub fn from_bigint(value: &Bigint) -> Option<Self> {
+        let limit = if SIGNED { Self::BITS as u64 - 1 } else { Self::BITS as u64 };
+        let fits = if value.is_negative() {
+            SIGNED && (value.abs() - Bigint::one()).bits() <= limit
+        } else {
+            value.bits() <= limit
+        };
+
+        if !fits {
+            return None;
+        }
+
+        let halves = value.to_twos_complement(LIMBS * 4);
+        let mut limbs = [0u64; LIMBS];
+        for (i, half) in halves.iter().enumerate() {
+            limbs[i / 4] |= (*half as u64) << (16 * (i % 4));
+        }
+
+        return Some(Self { limbs });
+    }
+
+    pub fn overflowing_add(self, rhs: Self) -> (Self, bool) {
+        let (limbs, carrier) = add_words(&self.limbs, &rhs.limbs);
+        let res = Self { limbs };
+
+        if SIGNED {
+            return (res, self.is_negative() == rhs.is_negative() && res.is_negative() != self.is_negative());
+        }
+
+        return (res, carrier);
+    }
+
+    pub fn overflowing_sub(self, rhs: Self) -> (Self, bool) {
+        let (limbs, borrow) = sub_words(&self.limbs, &rhs.limbs);
+        let res = Self { limbs };
+
+        if SIGNED {
+            return (res, self.is_negative() != rhs.is_negative() && res.is_negative() != self.is_negative());
+        }
+
+        return (res, borrow);
+    }
+
+    pub fn overflowing_mul(self, rhs: Self) -> (Self, bool) {
+        // the low words of the product are the same for two's complement and for magnitudes
+        let mut wrapped = [0u64; LIMBS];
+        wrapped.copy_from_slice(&mul_words(&self.limbs, &rhs.limbs)[..LIMBS]);
+
+        let full = mul_words(&self.magnitude(), &rhs.magnitude());
+        let mut low = [0u64; LIMBS];
+        low.copy_from_slice(&full[..LIMBS]);
+
+        let negative = self.is_negative() != rhs.is_negative();
+        let overflow = full[LIMBS..].iter().any(|&limb| limb != 0) || Self::from_magnitude(low, negative).1;
+
+        return (Self { limbs: wrapped }, overflow);
+    }
+
+    /// Panics on division by zero, like the primitive integers. The only overflow is `MIN / -1`.
+    pub fn overflowing_div(self, rhs: Self) -> (Self, bool) {
+        if rhs.is_zero() {
+            panic!("attempt to divide by zero");
+        }
+
+        let (quotient, _) = divmod_words(&self.magnitude(), &rhs.magnitude());
+
+        return Self::from_magnitude(quotient, self.is_negative() != rhs.is_negative());
+    }
+
+    /// Panics on division by zero. The remainder has the sign of `self`, and `MIN % -1` overflows
+    /// to zero.
+    pub fn overflowing_rem(self, rhs: Self) -> (Self, bool) {
+        if rhs.is_zero() {
+            panic!("attempt to calculate the remainder with a divisor of zero");
+        }
+
+        let overflow = self.overflowing_div(rhs).1;
+        let (_, remainder) = divmod_words(&self.magnitude(), &rhs.magnitude());
+
+        return (Self::from_magnitude(remainder, self.is_negative()).0, overflow);
+    }
+
+    pub fn overflowing_neg(self) -> (Self, bool) {
+        return Self::from_magnitude(self.magnitude(), !self.is_negative());
+    }
+
+    pub fn checked_add(self, rhs: Self) -> Option<Self> {
+        return checked(self.overflowing_add(rhs));
+    }
+
+    pub fn checked_sub(self, rhs: Self) -> Option<Self> {
+        return checked(self.overflowing_sub(rhs));
+    }
+
+    pub fn checked_mul(self, rhs: Self) -> Option<Self> {
+        return checked(self.overflowing_mul(rhs));
+    }
+
+    /// `None` on division by zero too.
+    pub fn checked_div(self, rhs: Self) -> Option<Self> {
+        return if rhs.is_zero() { None } else { checked(self.overflowing_div(rhs)) };
+    }
+
+    /// `None` on division by zero too.
+    pub fn checked_rem(self, rhs: Self) -> Option<Self> {
+        return if rhs.is_zero() { None } else { checked(self.overflowing_rem(rhs)) };
+    }
+
+    pub fn checked_neg(self) -> Option<Self> {
+        return checked(self.overflowing_neg());
+    }
+
+    pub fn wrapping_add(self, rhs: Self) -> Self {
+        return self.overflowing_add(rhs).0;
+    }
+
+    pub fn wrapping_sub(self, rhs: Self) -> Self {
+        return self.overflowing_sub(rhs).0;
+    }
+
+    pub fn wrapping_mul(self, rhs: Self) -> Self {
+        return self.overflowing_mul(rhs).0;
+    }
+
+    pub fn wrapping_div(self, rhs: Self) -> Self {
+        return self.overflowing_div(rhs).0;
+    }
+
+    pub fn wrapping_rem(self, rhs: Self) -> Self {
+        return self.overflowing_rem(rhs).0;
+    }
+
+    pub fn wrapping_neg(self) -> Self {
+        return self.overflowing_neg().0;
+    }
+
+    pub fn saturating_add(self, rhs: Self) -> Self {
+        return match self.overflowing_add(rhs) {
+            (res, false) => res,
+            _ => if rhs.is_negative() { Self::MIN } else { Self::MAX },
+        }
+    }
+
+    pub fn saturating_sub(self, rhs: Self) -> Self {
+        return match self.overflowing_sub(rhs) {
+            (res, false) => res,
+            _ => if SIGNED && rhs.is_negative() { Self::MAX } else { Self::MIN },
+        }
+    }
+
+    pub fn saturating_mul(self, rhs: Self) -> Self {
+        return match self.overflowing_mul(rhs) {
+            (res, false) => res,
+            _ => if self.is_negative() != rhs.is_negative() { Self::MIN } else { Self::MAX },
+        }
+    }
+
+    /// Only `MIN / -1` saturates, to `MAX`. Panics on division by zero.
+    pub fn saturating_div(self, rhs: Self) -> Self {
+        return match self.overflowing_div(rhs) {
+            (res, false) => res,
+            _ => Self::MAX,
+        }
+    }
+
+    pub fn saturating_neg(self) -> Self {
+        return match self.overflowing_neg() {
+            (res, false) => res,
+            _ => if self.is_negative() { Self::MAX } else { Self::MIN },
+        }
+    }
+}
+
+fn checked<T>((value, overflow): (T, bool)) -> Option<T> {
+    return if overflow { None } else { Some(value) };
+}
+
+impl<const LIMBS: usize, const SIGNED: bool> Default for FixedInt<LIMBS, SIGNED> {
+    fn default() -> Self {
+        return Self::ZERO;
+    }
+}
+
+impl<const LIMBS: usize, const SIGNED: bool> From<FixedInt<LIMBS, SIGNED>> for Bigint {
+    fn from(value: FixedInt<LIMBS, SIGNED>) -> Self {
+        return value.to_bigint();
+    }
+}
+
+impl<const LIMBS: usize, const SIGNED: bool> TryFrom<&Bigint> for FixedInt<LIMBS, SIGNED> {
+    type Error = ArithmeticError;
+
+    fn try_from(value: &Bigint) -> Result<Self, Self::Error> {
+        return FixedInt::from_bigint(value).ok_or(ArithmeticError::Overflow);
+    }
+}
+
+/// Parses like `Bigint`; values out of range are a `ParseError` too.
+impl<const LIMBS: usize, const SIGNED: bool> FromStr for FixedInt<LIMBS, SIGNED> {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        return FixedInt::from_bigint(&Bigint::from_str(s)?).ok_or(ParseError);
+    }
+}
+
+impl<const LIMBS: usize, const SIGNED: bool> fmt::Display for FixedInt<LIMBS, SIGNED> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        return fmt::Display::fmt(&self.to_bigint(), f);
+    }
+}
+
+impl<const LIMBS: usize, const SIGNED: bool> PartialOrd for FixedInt<LIMBS, SIGNED> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Compares like the corresponding `Bigint` values, without converting to them.
+impl<const LIMBS: usize, const SIGNED: bool> Ord for FixedInt<LIMBS, SIGNED> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        if self.is_negative() != other.is_negative() {
+            return if self.is_negative() { Ordering::Less } else { Ordering::Greater };
+        }
+
+        // with equal signs, two's complement words compare like unsigned ones
+        return cmp_words(&self.limbs, &other.limbs);
+    }
+}
+
+impl<const LIMBS: usize, const SIGNED: bool> Add for FixedInt<LIMBS, SIGNED> {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        return self.checked_add(rhs).expect("attempt to add with overflow");
+    }
+}
+
+impl<const LIMBS: usize, const SIGNED: bool> Sub for FixedInt<LIMBS, SIGNED> {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        return self.checked_sub(rhs).expect("attempt to subtract with overflow");
+    }
+}
+
+impl<const LIMBS: usize, const SIGNED: bool> Mul for FixedInt<LIMBS, SIGNED> {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self {
+        return self.checked_mul(rhs).expect("attempt to multiply with overflow");
+    }
+}
+
+impl<const LIMBS: usize, const SIGNED: bool> Div for FixedInt<LIMBS, SIGNED> {
+    type Output = Self;
+
+    fn div(self, rhs: Self) -> Self {
+        let (res, overflow) = self.overflowing_div(rhs);
+        if overflow {
+            panic!("attempt to divide with overflow");
+        }
+
+        return res;
+    }
+}
+
+impl<const LIMBS: usize, const SIGNED: bool> Rem for FixedInt<LIMBS, SIGNED> {
+    type Output = Self;
+
+    fn rem(self, rhs: Self) -> Self {
+        let (res, overflow) = self.overflowing_rem(rhs);
+        if overflow {
+            panic!("attempt to calculate the remainder with overflow");
+        }
+
+        return res;
+    }
+}
+
+impl<const LIMBS: usize, const SIGNED: bool> Neg for FixedInt<LIMBS, SIGNED> {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        return self.checked_neg().expect("attempt to negate with overflow");
+    }
+}
+
+/// Human-oriented renderings of `Bigint`, for reports.
+pub mod formatting {
+    use super::{round_div, Bigint, RoundingMode};
+
+    const ONES: [&str; 20] = [
+        "zero", "one", "two", "three", "four", "five", "six", "seven", "eight", "nine", "ten",
+        "eleven", "twelve", "thirteen", "fourteen", "fifteen", "sixteen", "seventeen", "eighteen",
+        "nineteen",
+    ];
+
+    const TENS: [&str; 10] = [
+        "", "", "twenty", "thirty", "forty", "fifty", "sixty", "seventy", "eighty", "ninety",
+    ];
+
+    /// Short scale names, `SCALES[i]` is `10^(3i)`.
+    const SCALES: [&str; 22] = [
+        "", "thousand", "million", "billion", "trillion", "quadrillion", "quintillion", "sextillion",
+        "septillion", "octillion", "nonillion", "decillion", "undecillion", "duodecillion",
+        "tredecillion", "quattuordecillion", "quindecillion", "sexdecillion", "septendecillion",
+        "octodecillion", "novemdecillion", "vigintillion",
+    ];
+
+    fn digits_to_string(digits: &[u8]) -> String {
+        return digits.iter().map(|&digit| (b'0' + digit) as char).collect();
+    }
+
+    /// Scientific notation with `significant_digits` digits in the mantissa (at least one),
+    /// rounded half away from zero: `to_scientific(&1234567, 3) == "1.23e+6"`.
+    pub fn to_scientific(value: &Bigint, significant_digits: usize) -> String {
+        let significant_digits = std::cmp::max(significant_digits, 1);
+        let mut exponent = value.get_size().saturating_sub(1);
+        let mut mantissa = value.digits.to_vec();
+
+        if mantissa.len() > significant_digits {
+            let divisor = Bigint::power_of_ten(mantissa.len() - significant_digits);
+            mantissa = round_div(&value.abs(), &divisor, RoundingMode::HalfUp).digits.to_vec();
+
+            // rounding 9.99 up gives 10.0, which is one digit too many
+            if mantissa.len() > significant_digits {
+                mantissa.pop();
+                exponent += 1;
+            }
+        }
+
+        mantissa.resize(significant_digits, 0);
+
+        let mut res = String::new();
+        if value.is_negative() {
+            res.push('-');
+        }
+
+        res.push((b'0' + mantissa[0]) as char);
+        if significant_digits > 1 {
+            res.push('.');
+            res.push_str(&digits_to_string(&mantissa[1..]));
+        }
+
+        res.push_str(&format!("e+{}", exponent));
+
+        return res;
+    }
+
+    /// The digits in groups of three, with `separator` between them:
+    /// `to_grouped(&1234567, ",") == "1,234,567"`.
+    pub fn to_grouped(value: &Bigint, separator: &str) -> String {
+        let digits = value.to_string();
+        let (sign, digits) = digits.split_at(if value.is_negative() { 1 } else { 0 });
+        let mut res = sign.to_string();
+
+        for (i, char) in digits.chars().enumerate() {
+            if i != 0 && (digits.len() - i) % 3 == 0 {
+                res.push_str(separator);
+            }
+
+            res.push(char);
+        }
+
+        return res;
+    }
+
+    /// Spells a number from 1 to 999.
+    fn hundreds_to_words(value: usize) -> String {
+        let mut words: Vec<String> = vec![];
+
+        if value >= 100 {
+            words.push(format!("{} hundred", ONES[value / 100]));
+        }
+
+        let rest = value % 100;
+        if rest >= 20 {
+            words.push(if rest.is_multiple_of(10) {
+                TENS[rest / 10].to_string()
+            } else {
+                format!("{}-{}", TENS[rest / 10], ONES[rest % 10])
+            });
+        } else if rest > 0 {
+            words.push(ONES[rest].to_string());
+        }
+
+        return words.join(" ");
+    }
+
+    /// Spells a magnitude, given as decimal digits without leading zeros.
+    fn magnitude_to_words(digits: &[u8]) -> String {
+        let groups = digits.len().div_ceil(3);
+
+        // above the largest scale name, the part in front of it is spelled recursively, like
+        // "one thousand vigintillion"
+        if groups > SCALES.len() {
+            let split = digits.len() - 3 * (SCALES.len() - 1);
+            let (high, low) = digits.split_at(split);
+            let mut res = format!("{} {}", magnitude_to_words(high), SCALES[SCALES.len() - 1]);
+
+            let low_start = low.iter().position(|&digit| digit != 0);
+            if let Some(start) = low_start {
+                res.push(' ');
+                res.push_str(&magnitude_to_words(&low[start..]));
+            }
+
+            return res;
+        }
+
+        let mut words: Vec<String> = vec![];
+        for group in 0..groups {
+            let end = digits.len() - 3 * (groups - group - 1);
+            let start = end.saturating_sub(3);
+            let value = digits[start..end].iter().fold(0, |acc, &digit| acc * 10 + digit as usize);
+
+            if value != 0 {
+                let scale = SCALES[groups - group - 1];
+                words.push(if scale.is_empty() {
+                    hundreds_to_words(value)
+                } else {
+                    format!("{} {}", hundreds_to_words(value), scale)
+                });
+            }
+        }
+
+        return words.join(" ");
+    }
+
+    /// The number in English words, with short scale names:
+    /// `to_words(&-123) == "minus one hundred twenty-three"`.
+    pub fn to_words(value: &Bigint) -> String {
+        if value.is_zero() {
+            return ONES[0].to_string();
+        }
+
+        let words = magnitude_to_words(&value.digits);
+
+        return if value.is_negative() { format!("minus {}", words) } else { words };
+    }
+}
+
+/// The set of all `x` with `x ≡ residue (mod modulus)`, with `residue` in `0..modulus`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Congruence {
+    pub residue: Bigint,
+    pub modulus: Bigint,
+}
+
+impl Congruence {
+    pub fn contains(&self, x: &Bigint) -> bool {
+        return x.modulo(&self.modulus).unwrap() == self.residue;
+    }
+
+    /// The solutions in `0..bound`, in increasing order, produced lazily -- there can be very
+    /// many of them.
+    pub fn solutions_below<'a>(&'a self, bound: &'a Bigint) -> impl Iterator<Item = Bigint> + 'a {
+        return std::iter::successors(Some(self.residue.clone()), move |x| Some(x.clone() + self.modulus.clone()))
+            .take_while(move |x| x < bound);
+    }
+}
+
+/// Solves `a * x ≡ b (mod m)`. With `g = gcd(a, m)` there are solutions only if `g` divides `b`;
+/// then they form a single class modulo `m / g`, i.e. `g` solutions modulo `m`.
+pub fn solve_linear_congruence(a: &Bigint, b: &Bigint, m: &Bigint) -> Result<Congruence, ArithmeticError> {
+    if !m.is_positive() {
+        return Err(ArithmeticError::NonPositiveModulus);
+    }
+
+    let a = a.modulo(m)?;
+    let b = b.modulo(m)?;
+    let g = a.gcd(m);
+
+    // gcd(0, m) == m, so a == 0 ends up here too and works out
+    let (b_reduced, remainder) = b.div_rem(&g)?;
+    if !remainder.is_zero() {
+        return Err(ArithmeticError::NoSolution);
+    }
+
+    let modulus = m.clone() / g.clone();
+    let inverse = (a / g).mod_inverse(&modulus)?;
+    let residue = (b_reduced * inverse).modulo(&modulus)?;
+
+    return Ok(Congruence { residue, modulus });
+}
+
+/// Solves the system `x ≡ residue (mod modulus)` for every pair, by the Chinese remainder
+/// theorem generalised to moduli that aren't coprime. The answer is a single class modulo the
+/// lcm of the moduli, or `ArithmeticError::NoSolution` if two of the congruences contradict each
+/// other. An empty system is solved by every integer.
+pub fn chinese_remainder(system: &[(Bigint, Bigint)]) -> Result<Congruence, ArithmeticError> {
+    let mut res = Congruence { residue: Bigint::zero(), modulus: Bigint::one() };
+
+    for (residue, modulus) in system {
+        if !modulus.is_positive() {
+            return Err(ArithmeticError::NonPositiveModulus);
+        }
+
+        // x = res.residue + res.modulus * k must also satisfy the next congruence, so
+        // res.modulus * k ≡ residue - res.residue (mod modulus)
+        let difference = residue.clone() - res.residue.clone();
+        let k = solve_linear_congruence(&res.modulus, &difference, modulus)?;
+
+        let lcm = res.modulus.lcm(modulus);
+        let residue = (res.residue.clone() + res.modulus.clone() * k.residue).modulo(&lcm)?;
+        res = Congruence { residue, modulus: lcm };
+    }
+
+    return Ok(res);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn big(value: i128) -> Bigint {
+        return Bigint::from_str(&value.to_string()).unwrap();
+    }
+
+    /// One xorshift64 step, so the random tests below are the same on every run.
+    fn next_u64(state: &mut u64) -> u64 {
+        *state ^= *state << 13;
+        *state ^= *state >> 7;
+        *state ^= *state << 17;
+
+        return *state;
+    }
+
+    /// A random value with the given sign and up to 120 bits, so that sums and differences of two
+    /// of them still fit in an `i128`. Short lengths are as likely as long ones, which covers both
+    /// the short fast paths and the digit-by-digit ones.
+    fn random_i128(state: &mut u64, sign: Sign) -> i128 {
+        let bits = next_u64(state) % 121;
+        let wide = ((next_u64(state) as u128) << 64) | next_u64(state) as u128;
+        let magnitude = if bits == 0 { 0 } else { (wide >> (128 - bits)) as i128 };
+
+        return match sign {
+            Sign::Minus => -magnitude.max(1),
+            Sign::Zero => 0,
+            Sign::Plus => magnitude.max(1),
+        };
+    }
+
+    #[test]
+    fn add_sub_cmp_match_i128_for_every_sign_pair() {
+        let signs = [Sign::Minus, Sign::Zero, Sign::Plus];
+        let mut state = 0x5eed_0026;
+
+        for &left_sign in &signs {
+            for &right_sign in &signs {
+                for round in 0..2000 {
+                    let left = random_i128(&mut state, left_sign);
+                    let mut right = random_i128(&mut state, right_sign);
+                    // equal magnitudes are where carries and signs cancel
+                    if round % 10 == 0 && right != 0 {
+                        right = right.signum() * left.abs().max(1);
+                    }
+
+                    let context = format!("{} and {}", left, right);
+                    assert_eq!(big(left) + big(right), big(left + right), "{}", context);
+                    assert_eq!(big(left) - big(right), big(left - right), "{}", context);
+                    assert_eq!(big(left).cmp(&big(right)), left.cmp(&right), "{}", context);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn mul_and_div_rem_match_i128() {
+        let signs = [Sign::Minus, Sign::Zero, Sign::Plus];
+        let mut state = 0x5eed_0027;
+
+        for &left_sign in &signs {
+            for &right_sign in &signs {
+                for _ in 0..500 {
+                    let left = random_i128(&mut state, left_sign);
+                    let right = random_i128(&mut state, right_sign);
+                    // short enough for the product to fit in an `i128`
+                    let (short_left, short_right) = (left >> 60, right >> 60);
+
+                    let context = format!("{} and {}", left, right);
+                    let product = big(short_left) * big(short_right);
+                    assert_eq!(product, big(short_left * short_right), "{}", context);
+                    if right == 0 {
+                        let error = Err(ArithmeticError::DivideByZero);
+                        assert_eq!(big(left).div_rem(&big(right)), error, "{}", context);
+                        continue;
+                    }
+
+                    let (quotient, remainder) = big(left).div_rem(&big(right)).unwrap();
+                    assert_eq!(quotient, big(left / right), "{}", context);
+                    assert_eq!(remainder, big(left % right), "{}", context);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn div_rem_of_long_numbers_is_exact() {
+        let mut state = 0x5eed_1027;
+        // a 7 and then `words` random groups of 19 digits
+        let random_long = |state: &mut u64, words: usize| -> Bigint {
+            let digits: String = (0..words)
+                .map(|_| format!("{:019}", next_u64(state) % 10_000_000_000_000_000_000))
+                .collect();
+            return Bigint::from_str(&format!("7{}", digits)).unwrap();
+        };
+
+        for (dividend_words, divisor_words) in [(1, 1), (3, 1), (8, 2), (20, 10), (30, 29)] {
+            let dividend = random_long(&mut state, dividend_words);
+            let divisor = random_long(&mut state, divisor_words);
+
+            for (left, right) in [
+                (dividend.clone(), divisor.clone()),
+                (dividend.get_inversed(), divisor.clone()),
+                (dividend.clone(), divisor.get_inversed()),
+                (dividend.get_inversed(), divisor.get_inversed()),
+            ] {
+                let (quotient, remainder) = left.div_rem(&right).unwrap();
+                assert_eq!(quotient.clone() * right.clone() + remainder.clone(), left);
+                assert!(remainder.abs() < right.abs());
+                assert!(remainder.is_zero() || remainder.sign() == left.sign());
+            }
+
+            // a product divides evenly, and a remainder just below the divisor stays one
+            let product = dividend.clone() * divisor.clone();
+            assert_eq!(product.div_rem(&divisor).unwrap(), (dividend.clone(), Bigint::zero()));
+            let below = divisor.clone() - Bigint::one();
+            assert_eq!((product + below.clone()).div_rem(&divisor).unwrap(), (dividend, below));
+        }
+    }
+
+    #[test]
+    fn modular_arithmetic_vectors() {
+        let (two, three, four, seven) = (big(2), big(3), big(4), big(7));
+
+        assert_eq!(three.modpow(&big(-1), &seven), Ok(big(5)));
+        assert_eq!(three.modpow(&big(-2), &seven), Ok(big(4)));
+        assert_eq!(two.modpow(&big(-1), &four), Err(ArithmeticError::NotInvertible));
+        assert_eq!(three.modpow(&big(5), &big(0)), Err(ArithmeticError::NonPositiveModulus));
+        assert_eq!(big(-3).modpow(&three, &seven), Ok(big(1)));
+        assert_eq!(three.modpow(&big(0), &big(1)), Ok(big(0)));
+        let modulus = big(1_000_000_007);
+        assert_eq!(two.modpow(&big(1_000_000_006), &modulus), Ok(big(1)));
+
+        assert_eq!(big(-7).modulo(&three), Ok(big(2)));
+        assert_eq!(three.mod_inverse(&seven), Ok(big(5)));
+        assert_eq!(big(-3).mod_inverse(&seven), Ok(big(2)));
+        assert_eq!(big(6).mod_inverse(&big(9)), Err(ArithmeticError::NotInvertible));
+
+        let mut state = 0x5eed_2027;
+        for _ in 0..500 {
+            let left = random_i128(&mut state, Sign::Minus) >> (next_u64(&mut state) % 100);
+            let right = random_i128(&mut state, Sign::Plus) >> (next_u64(&mut state) % 100);
+            let flip = next_u64(&mut state).is_multiple_of(2);
+            let (left, right) = if flip { (-left, right) } else { (left, -right) };
+
+            let (g, x, y) = big(left).extended_gcd(&big(right));
+            assert_eq!(g, big(left).gcd(&big(right)), "{} and {}", left, right);
+            assert_eq!(big(left) * x + big(right) * y, g, "{} and {}", left, right);
+        }
+    }
+
+    #[test]
+    fn roots_and_logarithms_at_exact_powers() {
+        let powers = [(2, 2), (3, 3), (10, 5), (12345, 7), (999_999_937, 4), (2, 100), (7, 41)];
+        for (base, k) in powers {
+            let power = big(base).pow(k);
+            let context = format!("{}^{}", base, k);
+
+            assert_eq!(power.nth_root(k), Ok(big(base)), "{}", context);
+            assert_eq!((power.clone() - big(1)).nth_root(k), Ok(big(base - 1)), "{}", context);
+            assert_eq!((power.clone() + big(1)).nth_root(k), Ok(big(base)), "{}", context);
+            if k % 2 == 1 {
+                assert_eq!(power.get_inversed().nth_root(k), Ok(big(-base)), "{}", context);
+                let below = power.get_inversed() + big(1);
+                assert_eq!(below.nth_root(k), Ok(big(1 - base)), "{}", context);
+            }
+
+            assert_eq!(power.ilog(&big(base)), Ok(k as u64), "{}", context);
+            assert_eq!((power.clone() - big(1)).ilog(&big(base)), Ok(k as u64 - 1), "{}", context);
+            assert_eq!((power + big(1)).ilog(&big(base)), Ok(k as u64), "{}", context);
+        }
+
+        let two_to_100 = big(2).pow(100);
+        assert_eq!(two_to_100.ilog2(), Ok(100));
+        assert_eq!((two_to_100.clone() - big(1)).ilog2(), Ok(99));
+        assert_eq!(two_to_100.ilog10(), Ok(30));
+        assert_eq!(big(1000).ilog10(), Ok(3));
+        assert_eq!(big(999).ilog10(), Ok(2));
+        assert_eq!(big(1).ilog(&big(10)), Ok(0));
+        assert_eq!(big(0).ilog2(), Err(ArithmeticError::NonPositiveLogarithm));
+        assert_eq!(big(-8).ilog(&big(2)), Err(ArithmeticError::NonPositiveLogarithm));
+        assert_eq!(big(8).ilog(&big(1)), Err(ArithmeticError::InvalidLogarithmBase));
+
+        assert_eq!(big(99).sqrt(), Ok(big(9)));
+        assert_eq!(big(100).sqrt(), Ok(big(10)));
+        assert!(big(144).is_perfect_square() && !big(145).is_perfect_square());
+        assert_eq!(big(-8).nth_root(2), Err(ArithmeticError::NegativeRoot));
+        assert_eq!(big(8).nth_root(0), Err(ArithmeticError::ZeroRootDegree));
+        assert_eq!(big(0).nth_root(3), Ok(big(0)));
+        assert_eq!(big(-5).nth_root(1), Ok(big(-5)));
+    }
+
+    #[test]
+    fn roots_of_a_high_degree_are_one() {
+        assert_eq!(big(5).nth_root(20_000), Ok(big(1)));
+        assert_eq!(big(5).nth_root(1_000_000), Ok(big(1)));
+        assert_eq!(big(-5).nth_root(u32::MAX), Ok(big(-1)));
+        assert_eq!(big(i128::MAX).nth_root(127), Ok(big(1)));
+        assert_eq!(big(i128::MAX).nth_root(126), Ok(big(2)));
+        assert_eq!(big(1 << 64).nth_root(64), Ok(big(2)));
+        assert_eq!(big((1 << 64) - 1).nth_root(64), Ok(big(1)));
+    }
+
+    #[test]
+    fn bits_far_above_the_value_are_the_sign() {
+        let five = big(5);
+        let minus_five = big(-5);
+
+        assert!(!five.bit(u64::MAX));
+        assert!(!five.bit(1 << 36));
+        assert!(minus_five.bit(u64::MAX));
+        assert!(minus_five.bit(1 << 36));
+        assert_eq!((five.bit(2), five.bit(1), five.bit(0)), (true, false, true));
+        assert_eq!((minus_five.bit(2), minus_five.bit(1), minus_five.bit(0)), (false, true, true));
+
+        let mut value = five.clone();
+        value.set_bit(u64::MAX, false);
+        value.set_bit(1 << 36, false);
+        assert_eq!(value, five);
+        value.set_bit(15, true);
+        assert_eq!(value, big(32773));
+
+        let mut value = minus_five.clone();
+        value.set_bit(u64::MAX, true);
+        assert_eq!(value, minus_five);
+        value.set_bit(20, false);
+        assert_eq!(value, big(-1048581));
+    }
+
+    #[test]
+    fn primality_vectors() {
+        // Carmichael numbers fool the Fermat test for every coprime base
+        for carmichael in [561, 1105, 41041, 3215031751] {
+            assert_eq!(big(carmichael).is_prime_deterministic(), Some(false), "{}", carmichael);
+            assert!(!big(carmichael).is_probable_prime(32), "{}", carmichael);
+        }
+        // 1501081 * 3002161 * 4503241, a Chernick Carmichael number above 2^64
+        let carmichael = Bigint::from_str("20293796286020108881").unwrap();
+        assert_eq!(carmichael.is_prime_deterministic(), None);
+        assert!(!carmichael.is_probable_prime(32));
+
+        let mersenne_61 = big((1 << 61) - 1);
+        assert_eq!(mersenne_61.is_prime_deterministic(), Some(true));
+        assert!(mersenne_61.is_probable_prime(32));
+        let mersenne_127 = big(i128::MAX);
+        assert_eq!(mersenne_127.is_prime_deterministic(), None);
+        assert!(mersenne_127.is_probable_prime(32));
+        assert!(!(mersenne_127.clone() + big(2)).is_probable_prime(32));
+
+        let next = big(u64::MAX as i128).next_prime();
+        assert_eq!(next, Bigint::from_str("18446744073709551629").unwrap());
+        assert_eq!(big((1 << 61) - 2).next_prime(), mersenne_61);
+
+        for small in [-7, -2, -1, 0, 1] {
+            assert_eq!(big(small).is_prime_deterministic(), Some(false), "{}", small);
+            assert!(!big(small).is_probable_prime(32), "{}", small);
+            assert_eq!(big(small).next_prime(), big(2), "{}", small);
+        }
+        assert_eq!(big(2).next_prime(), big(3));
+        assert!(big(2).is_probable_prime(32));
+    }
+
+    #[test]
+    fn wire_format_vectors() {
+        assert_eq!(big(0).to_wire_bytes(), [0]);
+        assert_eq!(big(1).to_wire_bytes(), [1, 2]);
+        assert_eq!(big(-1).to_wire_bytes(), [1, 1]);
+        assert_eq!(big(-129).to_wire_bytes(), [2, 1, 1]);
+        assert_eq!(big(128).to_wire_bytes(), [2, 0, 1]);
+
+        // a payload of 128 bytes needs a two-byte length
+        let long = big(1) << 1016;
+        let encoded = long.to_wire_bytes();
+        assert_eq!(&encoded[..2], [0x80, 0x01]);
+        assert_eq!(encoded.len(), 130);
+
+        let values = [big(0), big(-1), big(300), long.get_inversed(), big(i128::MIN), long];
+        let mut stream: Vec<u8> = Vec::new();
+        for value in &values {
+            value.encode_to(&mut stream).unwrap();
+        }
+
+        let decoded: Vec<Bigint> = WireDecoder::new(&stream[..]).map(Result::unwrap).collect();
+        assert_eq!(decoded, values);
+
+        // cutting the stream anywhere inside a number is an error, between two numbers it isn't
+        let boundaries = [1, 3, 6];
+        for cut in 0..=6 {
+            let mut decoder = WireDecoder::new(&stream[..cut]);
+            let read = decoder.by_ref().take_while(Result::is_ok).count();
+
+            assert_eq!(read, boundaries.iter().filter(|&&end| end <= cut).count(), "{}", cut);
+            assert!(decoder.next().is_none(), "{}", cut);
+        }
+        assert!(matches!(Bigint::decode_from(&stream[3..5]), Err(DecodeError::Truncated)));
+        assert!(matches!(Bigint::decode_from(&[][..]), Err(DecodeError::Truncated)));
+    }
+
+    #[test]
+    fn wire_decoder_stops_after_an_error() {
+        let seven = big(7).to_wire_bytes();
+
+        // eleven length bytes can't fit a `u64`
+        let mut overlong = vec![0x80; 10];
+        overlong.push(0x01);
+        overlong.extend_from_slice(&seven);
+        let mut decoder = WireDecoder::new(&overlong[..]);
+        assert!(matches!(decoder.next(), Some(Err(DecodeError::LengthOverflow))));
+        assert!(decoder.next().is_none());
+
+        // the tenth byte may only add the top bit
+        let mut too_long = vec![0xFF; 9];
+        too_long.push(0x02);
+        assert!(matches!(Bigint::decode_from(&too_long[..]), Err(DecodeError::LengthOverflow)));
+        too_long[9] = 0x01;
+        assert!(matches!(Bigint::decode_from(&too_long[..]), Err(DecodeError::Truncated)));
+
+        // a length that runs past the input, with a valid number behind it
+        let mut truncated = vec![0x81];
+        truncated.extend_from_slice(&seven);
+        let mut decoder = WireDecoder::new(&truncated[..]);
+        assert!(matches!(decoder.next(), Some(Err(DecodeError::Truncated))));
+        assert!(decoder.next().is_none());
+    }
+
+    #[test]
+    fn rationals_are_reduced_with_a_positive_denominator() {
+        let ratio = |numer: i128, denom: i128| BigRational::new(big(numer), big(denom)).unwrap();
+        let parse = |text: &str| BigRational::from_str(text);
+
+        let half = ratio(-3, -6);
+        assert_eq!((half.numer(), half.denom()), (&big(1), &big(2)));
+        let minus_half = ratio(4, -8);
+        assert_eq!((minus_half.numer(), minus_half.denom()), (&big(-1), &big(2)));
+        let zero = ratio(0, -5);
+        assert_eq!((zero.numer(), zero.denom()), (&big(0), &big(1)));
+        assert_eq!(BigRational::new(big(1), big(0)), Err(ArithmeticError::DivideByZero));
+        assert_eq!(ratio(0, 3).recip(), Err(ArithmeticError::DivideByZero));
+
+        assert_eq!(ratio(1, 2) + ratio(1, 3), ratio(5, 6));
+        assert_eq!(ratio(1, 2) - ratio(1, 3), ratio(1, 6));
+        assert_eq!(ratio(1, 2) - ratio(1, 2), ratio(0, 1));
+        assert_eq!(ratio(-2, 3) * ratio(9, 4), ratio(-3, 2));
+        assert_eq!(ratio(-2, 3) / ratio(-4, 9), ratio(3, 2));
+        assert_eq!(ratio(7, 2) % ratio(1, 1), ratio(1, 2));
+        assert_eq!(ratio(-7, 2) % ratio(1, 1), ratio(-1, 2));
+        assert!(ratio(-1, 2) < ratio(-1, 3) && ratio(1, 3) < ratio(1, 2));
+
+        assert_eq!(ratio(-7, 2).round(RoundingMode::HalfEven), big(-4));
+        assert_eq!(ratio(-7, 2).round(RoundingMode::Floor), big(-4));
+        assert_eq!(ratio(-7, 2).round(RoundingMode::Truncate), big(-3));
+        assert_eq!(ratio(2, 3).to_decimal_string(3, RoundingMode::HalfUp), "0.667");
+        assert_eq!(ratio(-1, 8).to_decimal_string(2, RoundingMode::HalfEven), "-0.12");
+
+        for text in ["0", "-3/4", "5/6", "-12", "123456789012345678901234567891/7"] {
+            assert_eq!(parse(text).unwrap().to_string(), text);
+        }
+        assert_eq!(parse("6/-8").unwrap(), ratio(-3, 4));
+        assert_eq!(parse("+6/+8").unwrap(), ratio(3, 4));
+        assert_eq!(parse("-12.375").unwrap(), ratio(-99, 8));
+        assert_eq!(parse(".5").unwrap(), ratio(1, 2));
+
+        let invalid = ["", "/", "-/4", "+/4", "/4", "3/", "3/-", "3/+", "1/0", "1/2/3", "1.5/2", ".", "x"];
+        for text in invalid {
+            assert!(parse(text).is_err(), "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn decimal_rounding_and_division() {
+        let decimal = |text: &str| BigDecimal::from_str(text).unwrap();
+        let round = |text: &str, mode: RoundingMode| decimal(text).rescale(0, mode).to_string();
+
+        let modes = [
+            RoundingMode::HalfEven,
+            RoundingMode::HalfUp,
+            RoundingMode::Floor,
+            RoundingMode::Ceiling,
+            RoundingMode::Truncate,
+        ];
+        let expected = [
+            ("2.5", ["2", "3", "2", "3", "2"]),
+            ("3.5", ["4", "4", "3", "4", "3"]),
+            ("-2.5", ["-2", "-3", "-3", "-2", "-2"]),
+            ("-3.5", ["-4", "-4", "-4", "-3", "-3"]),
+            ("2.51", ["3", "3", "2", "3", "2"]),
+            ("-2.49", ["-2", "-2", "-3", "-2", "-2"]),
+            ("-0.5", ["0", "-1", "-1", "0", "0"]),
+            ("7.000", ["7", "7", "7", "7", "7"]),
+        ];
+        for (text, rounded) in expected {
+            for (mode, want) in modes.iter().zip(rounded) {
+                assert_eq!(round(text, *mode), want, "{} {:?}", text, mode);
+            }
+        }
+
+        assert_eq!(decimal("1.25").rescale(4, RoundingMode::Floor).to_string(), "1.2500");
+        assert_eq!(decimal("-1.25").rescale(1, RoundingMode::HalfEven).to_string(), "-1.2");
+        assert_eq!(decimal("1.0"), decimal("1.00"));
+        assert_eq!((decimal("1.5") + decimal("-0.25")).to_string(), "1.25");
+        assert_eq!((decimal("1.5") * decimal("-0.25")).to_string(), "-0.375");
+        assert_eq!(decimal("-.5").to_string(), "-0.5");
+        for text in ["", ".", "-", "1.2.3", "1-2", "+-1", "1e5"] {
+            assert!(BigDecimal::from_str(text).is_err(), "{:?}", text);
+        }
+
+        let divide = |left: &str, right: &str, scale: u32, mode: RoundingMode| {
+            decimal(left).div_to_scale(&decimal(right), scale, mode).map(|res| res.to_string())
+        };
+        assert_eq!(divide("1", "3", 5, RoundingMode::HalfEven), Ok("0.33333".to_string()));
+        assert_eq!(divide("2", "3", 5, RoundingMode::HalfEven), Ok("0.66667".to_string()));
+        assert_eq!(divide("2", "-3", 5, RoundingMode::Truncate), Ok("-0.66666".to_string()));
+        assert_eq!(divide("-1", "8", 2, RoundingMode::HalfEven), Ok("-0.12".to_string()));
+        assert_eq!(divide("-1", "-8", 2, RoundingMode::HalfUp), Ok("0.13".to_string()));
+        assert_eq!(divide("1.50", "0.5", 0, RoundingMode::HalfEven), Ok("3".to_string()));
+        let by_zero = divide("1", "0.000", 2, RoundingMode::HalfEven);
+        assert_eq!(by_zero, Err(ArithmeticError::DivideByZero));
+    }
+
+    #[test]
+    fn decimal_scales_that_overflow_are_errors() {
+        let tiny = BigDecimal::new(big(1), u32::MAX);
+        let tenth = BigDecimal::new(big(1), 1);
+
+        assert_eq!(tiny.checked_mul(&tenth).err(), Some(ArithmeticError::Overflow));
+        assert_eq!(tiny.checked_mul(&BigDecimal::from_integer(big(3))).unwrap().scale(), u32::MAX);
+        let quotient = tenth.div_to_scale(&tiny, 1, RoundingMode::HalfEven);
+        assert_eq!(quotient.err(), Some(ArithmeticError::Overflow));
+        let quotient = tenth.div_to_scale(&tenth, u32::MAX, RoundingMode::HalfEven);
+        assert_eq!(quotient.err(), Some(ArithmeticError::Overflow));
+    }
+
+    #[test]
+    fn mod_int_matches_modpow_for_every_kind_of_modulus() {
+        let mut rng = XorShift64::new(34);
+        // odd, even, a multiple of 5, a multiple of 10, and the edge cases 1 and 2
+        let moduli = [big(1_000_000_007), big(1 << 70), big(5_000_015), big(1230), big(1), big(2)];
+
+        for modulus in moduli {
+            let context = Montgomery::new(modulus.clone()).unwrap();
+            let reduce = |value: Bigint| value.modulo(&modulus).unwrap();
+
+            for _ in 0..20 {
+                let wide = Bigint::random_below(&mut rng, &(modulus.clone() * big(3))).unwrap();
+                let left = wide - modulus.clone();
+                let right = Bigint::random_below(&mut rng, &modulus).unwrap();
+                let exponent = Bigint::random_bits(&mut rng, 80);
+                let (a, b) = (context.element(&left), context.element(&right));
+                let text = format!("{} and {} mod {}", left, right, modulus);
+
+                assert_eq!(a.to_bigint(), reduce(left.clone()), "{}", text);
+                let product = (a.clone() * b.clone()).to_bigint();
+                assert_eq!(product, reduce(left.clone() * right.clone()), "{}", text);
+                let sum = (a.clone() + b.clone()).to_bigint();
+                assert_eq!(sum, reduce(left.clone() + right.clone()), "{}", text);
+                let difference = (a.clone() - b.clone()).to_bigint();
+                assert_eq!(difference, reduce(left.clone() - right.clone()), "{}", text);
+                assert_eq!((-a.clone()).to_bigint(), reduce(left.get_inversed()), "{}", text);
+
+                let power = a.pow(&exponent).unwrap().to_bigint();
+                assert_eq!(power, left.modpow(&exponent, &modulus).unwrap(), "{}", text);
+                let inverse = a.pow(&big(-1)).map(|inverse| inverse.to_bigint());
+                assert_eq!(inverse, left.modpow(&big(-1), &modulus), "{}", text);
+            }
+        }
+
+        assert_eq!(Montgomery::new(big(0)).err(), Some(ArithmeticError::NonPositiveModulus));
+        assert_eq!(Montgomery::new(big(-7)).err(), Some(ArithmeticError::NonPositiveModulus));
+    }
+
+    #[test]
+    fn calculator_precedence_and_associativity() {
+        let mut calculator = Calculator::new();
+        let mut eval = |line: &str| {
+            calculator.eval_line(line).map(|value| value.map(|value| value.to_string()))
+        };
+
+        let expected = [
+            ("2 + 3 * 4", "14"),
+            ("(2 + 3) * 4", "20"),
+            ("7 - 2 - 1", "4"),
+            ("100 / 10 / 5", "2"),
+            ("2 ^ 3 ^ 2", "512"),
+            ("-2 ^ 2", "-4"),
+            ("(-2) ^ 2", "4"),
+            ("-7 / 2", "-3"),
+            ("-7 % 3", "-1"),
+            ("--3", "3"),
+            ("+-+3", "-3"),
+            ("0x1F + 0o17 + 0b101 + 0XfF", "306"),
+            ("gcd(12, 18) * sqrt(17) + pow(2, 10) - fact(5)", "928"),
+            ("x = 6 * 7", "42"),
+            ("x + 1", "43"),
+        ];
+        for (line, value) in expected {
+            assert_eq!(eval(line), Ok(Some(value.to_string())), "{}", line);
+        }
+
+        assert_eq!(eval("   "), Ok(None));
+        assert_eq!(eval("1 ^ 4000000000"), Ok(Some("1".to_string())));
+        assert_eq!(eval("(-1) ^ 4000000001"), Ok(Some("-1".to_string())));
+        assert_eq!(eval("0 ^ 4000000000"), Ok(Some("0".to_string())));
+        assert_eq!(eval("fact(0) + fact(1)"), Ok(Some("2".to_string())));
+    }
+
+    #[test]
+    fn calculator_errors_point_at_the_character() {
+        let eval = |line: &str| Calculator::new().eval_line(line).unwrap_err();
+        let error = |position: usize, kind: CalcErrorKind| CalcError { position, kind };
+        let arithmetic = |position: usize, error: ArithmeticError| {
+            CalcError { position, kind: CalcErrorKind::Arithmetic(error) }
+        };
+
+        assert_eq!(eval("1 + $"), error(4, CalcErrorKind::UnexpectedChar('$')));
+        assert_eq!(eval("1 +"), error(3, CalcErrorKind::UnexpectedEnd));
+        assert_eq!(eval("(1 + 2"), error(6, CalcErrorKind::UnexpectedEnd));
+        assert_eq!(eval("1 2"), error(2, CalcErrorKind::UnexpectedToken("2".to_string())));
+        assert_eq!(eval("0x"), error(0, CalcErrorKind::InvalidNumber("0x".to_string())));
+        assert_eq!(eval("12ab"), error(0, CalcErrorKind::InvalidNumber("12ab".to_string())));
+        assert_eq!(eval("1 / (2 - 2)"), arithmetic(2, ArithmeticError::DivideByZero));
+        assert_eq!(eval("sqrt(-4)"), arithmetic(0, ArithmeticError::NegativeRoot));
+        assert_eq!(eval("y + 1"), error(0, CalcErrorKind::UnknownVariable("y".to_string())));
+        assert_eq!(eval("foo(1)"), error(0, CalcErrorKind::UnknownFunction("foo".to_string())));
+        let name = "gcd".to_string();
+        let wrong_count = CalcErrorKind::WrongArgumentCount { name, expected: 2, found: 1 };
+        assert_eq!(eval("gcd(1)"), error(0, wrong_count));
+        assert_eq!(eval("2 ^ (0 - 3)"), error(2, CalcErrorKind::NegativeExponent));
+        assert_eq!(eval("2 ^ 4294967296"), error(2, CalcErrorKind::ExponentTooLarge));
+
+        // results that would take minutes are refused right away
+        assert_eq!(eval("2 ^ 4000000000"), error(2, CalcErrorKind::ResultTooLarge));
+        assert_eq!(eval("pow(10, 100001)"), error(0, CalcErrorKind::ResultTooLarge));
+        assert_eq!(eval("fact(4000000000)"), error(0, CalcErrorKind::ResultTooLarge));
+        assert_eq!(eval("fact(30000)"), error(0, CalcErrorKind::ResultTooLarge));
+
+        // positions count characters, not bytes -- both spaces here take more than one byte
+        assert_eq!(eval("1 + é"), error(4, CalcErrorKind::UnexpectedChar('é')));
+        let unknown = CalcErrorKind::UnknownVariable("y".to_string());
+        assert_eq!(eval("\u{a0}\u{3000}y"), error(2, unknown));
+        assert_eq!(eval("1\u{3000}+\u{a0}"), error(3, CalcErrorKind::UnexpectedEnd));
+        assert_eq!(eval("\u{3000}(1 2)").to_string(), "column 5: unexpected '2'");
+
+        let mut output: Vec<u8> = Vec::new();
+        run_repl("1 +\u{3000}\u{a0}z\n3 * 3\nquit\n4\n".as_bytes(), &mut output).unwrap();
+        let expected = ">        ^\nerror: column 6: unknown variable 'z'\n> 9\n> ";
+        assert_eq!(String::from_utf8(output).unwrap(), expected);
+    }
+
+    #[test]
+    fn combinatorics_vectors() {
+        let number = |text: &str| Bigint::from_str(text).unwrap();
+
+        assert_eq!(Bigint::factorial(0), big(1));
+        assert_eq!(Bigint::factorial(25), number("15511210043330985984000000"));
+        assert_eq!(
+            Bigint::factorial(100),
+            number(concat!(
+                "93326215443944152681699238856266700490715968264381621468592963895217599993229915",
+                "608941463976156518286253697920827223758251185210916864000000000000000000000000",
+            )),
+        );
+
+        assert_eq!(Bigint::binomial(50, 25), number("126410606437752"));
+        assert_eq!(Bigint::binomial(50, 0), big(1));
+        assert_eq!(Bigint::binomial(5, 6), big(0));
+        assert_eq!(Bigint::binomial(0, 1), big(0));
+
+        assert_eq!(Bigint::fibonacci(0), big(0));
+        assert_eq!(Bigint::fibonacci(1), big(1));
+        assert_eq!(Bigint::fibonacci(100), number("354224848179261915075"));
+        let f1000 = Bigint::fibonacci(1000);
+        assert_eq!(f1000.get_size(), 209);
+        assert_eq!(
+            f1000,
+            number(concat!(
+                "43466557686937456435688527675040625802564660517371780402481729089536555417949051",
+                "89040387984007925516929592259308032263477520968962323987332247116164299644090653",
+                "3187938298969649928516003704476137795166849228875",
+            )),
+        );
+
+        assert_eq!(Bigint::lucas(0), big(2));
+        assert_eq!(Bigint::lucas(1), big(1));
+        assert_eq!(Bigint::lucas(10), big(123));
+    }
+
+    #[test]
+    fn xorshift_is_determined_by_its_seed() {
+        let mut rng = XorShift64::new(1);
+        let first: Vec<u64> = (0..3).map(|_| rng.next_u64()).collect();
+        assert_eq!(first, [1082269761, 1152992998833853505, 11177516664432764457]);
+
+        let (mut left, mut right) = (XorShift64::new(0x5eed), XorShift64::new(0x5eed));
+        assert!((0..1000).all(|_| left.next_u64() == right.next_u64()));
+        let (mut zero, mut other) = (XorShift64::new(0), XorShift64::new(1));
+        assert_ne!(zero.next_u64(), 0);
+        assert_ne!(zero.next_u64(), other.next_u64());
+
+        let draw = |seed: u64| {
+            let mut rng = XorShift64::new(seed);
+            return Bigint::random_bits(&mut rng, 500);
+        };
+        assert_eq!(draw(38), draw(38));
+        assert_ne!(draw(38), draw(39));
+    }
+
+    #[test]
+    fn random_values_stay_in_bounds() {
+        let mut rng = XorShift64::new(38);
+
+        for bits in [0, 1, 2, 15, 16, 17, 63, 64, 65, 200] {
+            let mut top_bit_seen = bits == 0;
+            for _ in 0..50 {
+                let value = Bigint::random_bits(&mut rng, bits);
+                assert!(!value.is_negative() && value.bits() <= bits, "{} bits", bits);
+                top_bit_seen |= value.bits() == bits;
+
+                let exact = Bigint::random_with_bit_length(&mut rng, bits);
+                assert_eq!(exact.bits(), bits);
+            }
+            assert!(top_bit_seen, "{} bits", bits);
+        }
+
+        let mut seen = [false; 3];
+        for _ in 0..100 {
+            let value = Bigint::random_below(&mut rng, &big(3)).unwrap();
+            seen[value.to_u64().unwrap() as usize] = true;
+        }
+        assert_eq!(seen, [true; 3]);
+
+        let ranges = [(-5, -4), (-3, 3), (0, 1), (1 << 70, (1 << 70) + 7), (-(1 << 80), 1 << 80)];
+        for (low, high) in ranges {
+            for _ in 0..50 {
+                let value = Bigint::random_range(&mut rng, &big(low), &big(high)).unwrap();
+                assert!(big(low) <= value && value < big(high), "{} in {}..{}", value, low, high);
+            }
+        }
+
+        let empty = Err(ArithmeticError::EmptyRange);
+        assert_eq!(Bigint::random_below(&mut rng, &big(0)), empty);
+        assert_eq!(Bigint::random_below(&mut rng, &big(-3)), empty);
+        assert_eq!(Bigint::random_range(&mut rng, &big(4), &big(4)), empty);
+        assert_eq!(Bigint::random_range(&mut rng, &big(5), &big(-5)), empty);
+    }
+
+    #[test]
+    fn random_miller_rabin_bases_above_two_to_the_64() {
+        let two_to_64 = big(1 << 64);
+        let p = big(1 << 40).next_prime();
+        let q = p.next_prime();
+        let semiprime = p.clone() * q.clone();
+        assert!(semiprime > two_to_64 && semiprime.is_prime_deterministic().is_none());
+        assert!(!semiprime.is_probable_prime(8));
+        assert!(!(p.clone() * p).is_probable_prime(8));
+
+        for exponent in [89, 107, 127] {
+            let mersenne = big(2).pow(exponent) - big(1);
+            assert!(mersenne.is_probable_prime(8), "2^{} - 1", exponent);
+            assert!(!(mersenne * big(3)).is_probable_prime(8), "3 * (2^{} - 1)", exponent);
+        }
+        assert_eq!((two_to_64.clone() + big(13)).next_prime(), two_to_64 + big(37));
+    }
+
+    #[test]
+    fn binary_round_trip() {
+        let bytes = [0, 0, 0, 0, 0, 0, 0, 0, 1];
+        let two_to_64 = Bigint::from_str("18446744073709551616").unwrap();
+        assert_eq!(Bigint::from_bytes_le(Sign::Plus, &bytes), two_to_64);
+        assert_eq!(Bigint::from_bytes_le(Sign::Plus, &[0, 0, 0]), Bigint::zero());
+
+        let mut rng = XorShift64::new(39);
+        for bits in [1, 15, 16, 17, 31, 32, 33, 64, 1000, 20000] {
+            let value = Bigint::random_with_bit_length(&mut rng, bits).get_inversed();
+            let (sign, magnitude) = value.to_bytes_le();
+            assert_eq!(Bigint::from_bytes_le(sign, &magnitude), value, "{} bits", bits);
+            assert_eq!(Bigint::from_str_radix(&value.to_string(), 10).unwrap(), value);
+        }
+    }
+
+    #[test]
+    fn long_binary_values_with_zero_limbs_convert_exactly() {
+        for len in [511, 512, 513, 5000] {
+            let mut power = vec![0u8; len];
+            power.push(1);
+            let all_ones = vec![0xFF; len];
+
+            let power = Bigint::from_bytes_le(Sign::Plus, &power);
+            let all_ones = Bigint::from_bytes_le(Sign::Plus, &all_ones);
+            assert_eq!(all_ones.clone() + big(1), power, "{} bytes", len);
+            assert_eq!(power.bits(), len as u64 * 8 + 1);
+            assert_eq!(all_ones.to_bytes_le().1, vec![0xFF; len]);
+        }
+    }
+
+    /// Hands out at most `step` bytes per read, and an `Interrupted` error before every other one.
+    struct Trickle<'a> {
+        data: &'a [u8],
+        step: usize,
+        interrupt: bool,
+    }
+
+    impl<'a> Read for Trickle<'a> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            self.interrupt = !self.interrupt;
+            if self.interrupt {
+                return Err(std::io::Error::new(std::io::ErrorKind::Interrupted, "again"));
+            }
+
+            let len = std::cmp::min(std::cmp::min(self.step, buf.len()), self.data.len());
+            buf[..len].copy_from_slice(&self.data[..len]);
+            self.data = &self.data[len..];
+
+            return Ok(len);
+        }
+    }
+
+    #[test]
+    fn read_from_across_chunk_boundaries() {
+        let read = |text: &str, step: usize| {
+            Bigint::read_from(Trickle { data: text.as_bytes(), step, interrupt: false })
+        };
+        let invalid_at = |text: &str, step: usize| match read(text, step) {
+            Err(DecodeError::InvalidDigit(offset)) => offset,
+            other => panic!("{:?} for {:?}", other, text),
+        };
+
+        for step in [1, 2, 3, 7, IO_CHUNK_SIZE] {
+            assert_eq!(read("  -000123  \n", step).unwrap(), big(-123));
+            assert_eq!(read("+0", step).unwrap(), big(0));
+            assert_eq!(read("-000", step).unwrap().sign(), Sign::Zero);
+            assert_eq!(read("\t42", step).unwrap(), big(42));
+
+            for empty in ["", "   ", "-", "+", " +"] {
+                assert!(matches!(read(empty, step), Err(DecodeError::Truncated)), "{:?}", empty);
+            }
+            assert_eq!(invalid_at("12a34", step), 2);
+            assert_eq!(invalid_at("12 34", step), 3);
+            assert_eq!(invalid_at("1-2", step), 1);
+            assert_eq!(invalid_at("--1", step), 1);
+            assert_eq!(invalid_at("- 1", step), 1);
+            assert_eq!(invalid_at(" - ", step), 2);
+        }
+
+        // longer than a chunk, with the sign and the zeros in the first one
+        let digits: String = (0..3 * IO_CHUNK_SIZE)
+            .map(|i| (b'1' + (i % 9) as u8) as char)
+            .collect();
+        let text = format!("-00{}\n", digits);
+        let value = Bigint::from_str(&format!("-{}", digits)).unwrap();
+        assert_eq!(read(&text, IO_CHUNK_SIZE - 1).unwrap(), value);
+        assert_eq!(Bigint::read_from(text.as_bytes()).unwrap(), value);
+
+        let mut broken = text.clone().into_bytes();
+        broken[IO_CHUNK_SIZE + 5] = b'x';
+        let broken = String::from_utf8(broken).unwrap();
+        assert_eq!(invalid_at(&broken, 1000), (IO_CHUNK_SIZE + 5) as u64);
+
+        let mut written: Vec<u8> = Vec::new();
+        value.write_to(&mut written).unwrap();
+        assert_eq!(written, format!("-{}", digits).into_bytes());
+        written.clear();
+        big(0).write_to(&mut written).unwrap();
+        assert_eq!(written, b"0");
+    }
+
+    /// Checks every flavour of the arithmetic of one fixed-width type on values around its edges,
+    /// against the exact result in `Bigint`.
+    fn check_fixed_int_edges<const LIMBS: usize, const SIGNED: bool>() {
+        let min = FixedInt::<LIMBS, SIGNED>::MIN.to_bigint();
+        let max = FixedInt::<LIMBS, SIGNED>::MAX.to_bigint();
+        let modulus = big(1) << FixedInt::<LIMBS, SIGNED>::BITS;
+        let fixed = |value: &Bigint| FixedInt::<LIMBS, SIGNED>::from_bigint(value);
+        let wrap = |exact: &Bigint| {
+            let wrapped = exact.modulo(&modulus).unwrap();
+            return fixed(&if wrapped > max { wrapped - modulus.clone() } else { wrapped }).unwrap();
+        };
+        let clamp = |exact: &Bigint| fixed(&exact.clone().clamp(min.clone(), max.clone())).unwrap();
+
+        let half = big(1) << (FixedInt::<LIMBS, SIGNED>::BITS / 2);
+        let mut edges = vec![min.clone(), min.clone() + big(1), max.clone(), max.clone() - big(1)];
+        edges.extend([big(0), big(1), big(2), big(3), big(-1), big(-2), big(-3)]);
+        edges.extend([max.clone() / big(2), max.clone() / big(2) + big(1), min.clone() / big(2)]);
+        edges.extend([half.clone(), half.clone() - big(1), half.get_inversed(), half / big(2)]);
+        edges.retain(|value| fixed(value).is_some());
+
+        for left in &edges {
+            let a = fixed(left).unwrap();
+            let exact = left.get_inversed();
+            let context = format!("-({})", left);
+            assert_eq!(a.overflowing_neg(), (wrap(&exact), fixed(&exact).is_none()), "{}", context);
+            assert_eq!(a.checked_neg(), fixed(&exact), "{}", context);
+            assert_eq!(a.wrapping_neg(), wrap(&exact), "{}", context);
+            assert_eq!(a.saturating_neg(), clamp(&exact), "{}", context);
+            assert_eq!(a.to_string(), left.to_string());
+            assert_eq!(left.to_string().parse().ok(), Some(a));
+
+            for right in &edges {
+                let b = fixed(right).unwrap();
+                let context = format!("{} and {}", left, right);
+
+                let exact = left.clone() + right.clone();
+                let overflow = fixed(&exact).is_none();
+                assert_eq!(a.overflowing_add(b), (wrap(&exact), overflow), "{}", context);
+                assert_eq!(a.checked_add(b), fixed(&exact), "{}", context);
+                assert_eq!(a.wrapping_add(b), wrap(&exact), "{}", context);
+                assert_eq!(a.saturating_add(b), clamp(&exact), "{}", context);
+
+                let exact = left.clone() - right.clone();
+                let overflow = fixed(&exact).is_none();
+                assert_eq!(a.overflowing_sub(b), (wrap(&exact), overflow), "{}", context);
+                assert_eq!(a.checked_sub(b), fixed(&exact), "{}", context);
+                assert_eq!(a.wrapping_sub(b), wrap(&exact), "{}", context);
+                assert_eq!(a.saturating_sub(b), clamp(&exact), "{}", context);
+
+                let exact = left.clone() * right.clone();
+                let overflow = fixed(&exact).is_none();
+                assert_eq!(a.overflowing_mul(b), (wrap(&exact), overflow), "{}", context);
+                assert_eq!(a.checked_mul(b), fixed(&exact), "{}", context);
+                assert_eq!(a.wrapping_mul(b), wrap(&exact), "{}", context);
+                assert_eq!(a.saturating_mul(b), clamp(&exact), "{}", context);
+
+                if right.is_zero() {
+                    assert_eq!((a.checked_div(b), a.checked_rem(b)), (None, None), "{}", context);
+                    continue;
+                }
+
+                let (quotient, remainder) = left.div_rem(right).unwrap();
+                let overflow = fixed(&quotient).is_none();
+                assert_eq!(a.overflowing_div(b), (wrap(&quotient), overflow), "{}", context);
+                assert_eq!(a.checked_div(b), fixed(&quotient), "{}", context);
+                assert_eq!(a.wrapping_div(b), wrap(&quotient), "{}", context);
+                assert_eq!(a.saturating_div(b), clamp(&quotient), "{}", context);
+                // like on the primitive integers, `MIN % -1` overflows along with `MIN / -1`
+                assert_eq!(a.overflowing_rem(b), (wrap(&remainder), overflow), "{}", context);
+                let checked_remainder = if overflow { None } else { fixed(&remainder) };
+                assert_eq!(a.checked_rem(b), checked_remainder, "{}", context);
+                assert_eq!(a.wrapping_rem(b), wrap(&remainder), "{}", context);
+            }
+        }
+
+        assert!((max + big(1)).to_string().parse::<FixedInt<LIMBS, SIGNED>>().is_err());
+        assert!((min - big(1)).to_string().parse::<FixedInt<LIMBS, SIGNED>>().is_err());
+    }
+
+    #[test]
+    fn fixed_int_arithmetic_at_the_edges() {
+        check_fixed_int_edges::<2, false>();
+        check_fixed_int_edges::<4, false>();
+        check_fixed_int_edges::<8, false>();
+        check_fixed_int_edges::<2, true>();
+        check_fixed_int_edges::<4, true>();
+        check_fixed_int_edges::<8, true>();
+    }
+
+    #[test]
+    fn fixed_int_matches_the_primitive_128_bit_integers() {
+        let signed = [i128::MIN, i128::MIN + 1, -(1 << 64), -2, -1, 0, 1, 2, 1 << 64, i128::MAX];
+        let as_fixed = |value: i128| I128::from_str(&value.to_string()).unwrap();
+        let same = |fixed: (I128, bool), primitive: (i128, bool), context: &dyn fmt::Debug| {
+            let expected = (primitive.0.to_string(), primitive.1);
+            assert_eq!((fixed.0.to_string(), fixed.1), expected, "{:?}", context);
+        };
+
+        for &a in &signed {
+            same(as_fixed(a).overflowing_neg(), a.overflowing_neg(), &a);
+            for &b in &signed {
+                let (x, y, pair) = (as_fixed(a), as_fixed(b), (a, b));
+                same(x.overflowing_add(y), a.overflowing_add(b), &pair);
+                same(x.overflowing_sub(y), a.overflowing_sub(b), &pair);
+                same(x.overflowing_mul(y), a.overflowing_mul(b), &pair);
+                if b != 0 {
+                    same(x.overflowing_div(y), a.overflowing_div(b), &pair);
+                    same(x.overflowing_rem(y), a.overflowing_rem(b), &pair);
+                }
+            }
+        }
+
+        let unsigned = [0, 1, 2, 1 << 64, u128::MAX / 2, u128::MAX - 1, u128::MAX];
+        let as_fixed = |value: u128| U128::from_str(&value.to_string()).unwrap();
+        let same = |fixed: (U128, bool), primitive: (u128, bool), context: &dyn fmt::Debug| {
+            let expected = (primitive.0.to_string(), primitive.1);
+            assert_eq!((fixed.0.to_string(), fixed.1), expected, "{:?}", context);
+        };
+
+        for &a in &unsigned {
+            same(as_fixed(a).overflowing_neg(), a.overflowing_neg(), &a);
+            for &b in &unsigned {
+                let (x, y, pair) = (as_fixed(a), as_fixed(b), (a, b));
+                same(x.overflowing_add(y), a.overflowing_add(b), &pair);
+                same(x.overflowing_sub(y), a.overflowing_sub(b), &pair);
+                same(x.overflowing_mul(y), a.overflowing_mul(b), &pair);
+                if b != 0 {
+                    same(x.overflowing_div(y), a.overflowing_div(b), &pair);
+                    same(x.overflowing_rem(y), a.overflowing_rem(b), &pair);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn scientific_notation_rounds_and_carries() {
+        use formatting::to_scientific;
+
+        let cases = [
+            (0, 1, "0e+0"),
+            (0, 3, "0.00e+0"),
+            (7, 3, "7.00e+0"),
+            (1234567, 3, "1.23e+6"),
+            (1235000, 3, "1.24e+6"),
+            (-1234567, 0, "-1e+6"),
+            (999, 2, "1.0e+3"),
+            (9999, 3, "1.00e+4"),
+            (-996, 2, "-1.0e+3"),
+            (-994, 2, "-9.9e+2"),
+        ];
+
+        for (value, significant_digits, expected) in cases {
+            assert_eq!(to_scientific(&big(value), significant_digits), expected, "{}", value);
+        }
+    }
+
+    #[test]
+    fn grouped_digits() {
+        use formatting::to_grouped;
+
+        let cases = [
+            (0, "0"), (12, "12"), (123, "123"), (1234, "1,234"), (-123456, "-123,456"),
+            (-1234567, "-1,234,567"),
+            (i128::MAX, "170,141,183,460,469,231,731,687,303,715,884,105,727"),
+        ];
+
+        for (value, expected) in cases {
+            assert_eq!(to_grouped(&big(value), ","), expected);
+        }
+
+        assert_eq!(to_grouped(&big(1234567), "\u{202f}"), "1\u{202f}234\u{202f}567");
+    }
+
+    #[test]
+    fn words_at_the_scale_boundaries() {
+        use formatting::to_words;
+
+        let cases = [
+            (0, "zero"),
+            (-7, "minus seven"),
+            (19, "nineteen"),
+            (20, "twenty"),
+            (-123, "minus one hundred twenty-three"),
+            (1000, "one thousand"),
+            (1001, "one thousand one"),
+            (999_999, "nine hundred ninety-nine thousand nine hundred ninety-nine"),
+            (1_000_000, "one million"),
+            (1_000_010_000, "one billion ten thousand"),
+        ];
+
+        for (value, expected) in cases {
+            assert_eq!(to_words(&big(value)), expected);
+        }
+
+        let power = |exponent: usize| Bigint::power_of_ten(exponent);
+        assert_eq!(to_words(&power(63)), "one vigintillion");
+        assert_eq!(to_words(&(power(63) + big(1))), "one vigintillion one");
+        assert_eq!(to_words(&(power(63) - big(1))).split(' ').nth(3), Some("novemdecillion"));
+        assert_eq!(to_words(&power(66)), "one thousand vigintillion");
+        assert_eq!(to_words(&power(126).get_inversed()), "minus one vigintillion vigintillion");
+    }
+
+    fn is_inline(value: &Bigint) -> bool {
+        return matches!(value.digits, Digits::Inline { .. });
+    }
+
+    #[test]
+    fn digits_are_inline_up_to_nineteen_digits() {
+        let largest_inline = big(9_999_999_999_999_999_999);
+        let smallest_heap = big(10_000_000_000_000_000_000);
+
+        for value in [largest_inline.clone(), largest_inline.get_inversed(), big(0), big(1)] {
+            assert!(is_inline(&value), "{}", value);
+        }
+        for value in [smallest_heap.clone(), smallest_heap.get_inversed(), big(u64::MAX as i128)] {
+            assert!(!is_inline(&value), "{}", value);
+        }
+
+        let parsed: Bigint = "-0009999999999999999999".parse().unwrap();
+        assert!(is_inline(&parsed));
+        assert_eq!(parsed, largest_inline.get_inversed());
+        let parsed: Bigint = "+0010000000000000000000".parse().unwrap();
+        assert!(!is_inline(&parsed));
+        assert_eq!(parsed, smallest_heap);
+    }
+
+    #[test]
+    fn arithmetic_moves_digits_between_inline_and_heap() {
+        let largest_inline = big(9_999_999_999_999_999_999);
+        let smallest_heap = big(10_000_000_000_000_000_000);
+
+        let up = largest_inline.clone() + big(1);
+        assert!(!is_inline(&up));
+        assert_eq!(up, smallest_heap);
+
+        let down = smallest_heap.clone() - big(1);
+        assert!(is_inline(&down));
+        assert_eq!(down, largest_inline);
+
+        let negative_down = smallest_heap.get_inversed() + big(1);
+        assert!(is_inline(&negative_down));
+        assert_eq!(negative_down, largest_inline.get_inversed());
+
+        // both operands on the heap, the result inline
+        let long = big(123_456_789_012_345_678_901_234);
+        let difference = long.clone() - (long.clone() - big(42));
+        assert!(is_inline(&difference));
+        assert_eq!(difference, big(42));
+        assert!(is_inline(&(long.clone() - long)));
+
+        let product = big(3_333_333_333_333_333_333) * big(3);
+        assert!(is_inline(&product));
+        let product = big(3_333_333_333_333_333_334) * big(3);
+        assert!(!is_inline(&product));
+        assert_eq!(product, big(10_000_000_000_000_000_002));
+    }
+
+    fn congruence(residue: i128, modulus: i128) -> Congruence {
+        return Congruence { residue: big(residue), modulus: big(modulus) };
+    }
+
+    #[test]
+    fn linear_congruences() {
+        assert_eq!(solve_linear_congruence(&big(3), &big(2), &big(7)), Ok(congruence(3, 7)));
+        assert_eq!(solve_linear_congruence(&big(-3), &big(-2), &big(7)), Ok(congruence(3, 7)));
+        assert_eq!(solve_linear_congruence(&big(6), &big(4), &big(10)), Ok(congruence(4, 5)));
+        assert_eq!(solve_linear_congruence(&big(0), &big(20), &big(10)), Ok(congruence(0, 1)));
+
+        let no_solution = Err(ArithmeticError::NoSolution);
+        assert_eq!(solve_linear_congruence(&big(6), &big(3), &big(10)), no_solution);
+        assert_eq!(solve_linear_congruence(&big(0), &big(5), &big(10)), no_solution);
+        let non_positive = Err(ArithmeticError::NonPositiveModulus);
+        assert_eq!(solve_linear_congruence(&big(1), &big(1), &big(0)), non_positive);
+
+        // every small equation, against the solutions found by trying every x
+        for m in 1..13_i128 {
+            for a in -13..13 {
+                for b in -13..13 {
+                    let solves = |x: &i128| (a * x - b).rem_euclid(m) == 0;
+                    let expected: Vec<Bigint> = (0..m).filter(solves).map(big).collect();
+                    let res = solve_linear_congruence(&big(a), &big(b), &big(m));
+                    let actual: Vec<Bigint> = match res {
+                        Ok(res) => res.solutions_below(&big(m)).collect(),
+                        Err(error) => {
+                            assert_eq!(error, ArithmeticError::NoSolution);
+                            vec![]
+                        },
+                    };
+
+                    assert_eq!(actual, expected, "{} * x = {} (mod {})", a, b, m);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn chinese_remainder_systems() {
+        let system = |pairs: &[(i128, i128)]| -> Vec<(Bigint, Bigint)> {
+            return pairs.iter().map(|&(residue, modulus)| (big(residue), big(modulus))).collect();
+        };
+
+        assert_eq!(chinese_remainder(&[]), Ok(congruence(0, 1)));
+        assert_eq!(chinese_remainder(&system(&[(2, 3), (3, 5), (2, 7)])), Ok(congruence(23, 105)));
+        assert_eq!(chinese_remainder(&system(&[(2, 6), (8, 10)])), Ok(congruence(8, 30)));
+        assert_eq!(chinese_remainder(&system(&[(3, 4), (1, 6), (7, 8)])), Ok(congruence(7, 24)));
+        assert_eq!(chinese_remainder(&system(&[(-1, 3), (-1, 5)])), Ok(congruence(14, 15)));
+        assert_eq!(chinese_remainder(&system(&[(-7, 4), (-13, 6)])), Ok(congruence(5, 12)));
+
+        assert_eq!(chinese_remainder(&system(&[(1, 4), (2, 6)])), Err(ArithmeticError::NoSolution));
+        let non_positive = Err(ArithmeticError::NonPositiveModulus);
+        assert_eq!(chinese_remainder(&system(&[(1, 4), (0, 0)])), non_positive);
+
+        // coprime moduli far above 64 bits
+        let moduli = [(1 << 61) - 1, (1 << 89) - 1, 1_000_000_007];
+        let x: i128 = 123_456_789_012_345_678_901_234_567;
+        let pairs: Vec<(Bigint, Bigint)> = moduli.iter().map(|&m| (big(x % m), big(m))).collect();
+        let res = chinese_remainder(&pairs).unwrap();
+
+        assert!(res.contains(&big(x)));
+        assert_eq!(res.modulus, moduli.iter().fold(Bigint::one(), |acc, &m| acc * big(m)));
+        for (residue, modulus) in &pairs {
+            assert_eq!(&res.residue.modulo(modulus).unwrap(), residue);
+        }
+    }
+}
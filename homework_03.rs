@@ -54,12 +54,45 @@ pub fn take_and_skip(input: &str, target: char) -> Option<(&str, &str)> {
     }
 }
 
+/// What went wrong in a header or row, and where in the input.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub message: String,
+    /// Counts rows from 1; the header is record 0.
+    pub record: usize,
+    /// The physical line of the error, from 1. Differs from the line the record starts on when a
+    /// quoted value spans lines.
+    pub line: usize,
+    /// Bytes from the start of the input to the error.
+    pub byte_offset: u64,
+    /// The index of the value the error is in, if it is in one. Can be past the last column, for
+    /// a row with too many values.
+    pub column: Option<usize>,
+    pub column_name: Option<String>,
+    /// Up to 20 characters of the input on each side of the error.
+    pub snippet: String,
+}
+
+impl std::fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{} at record {}, line {}", self.message, self.record, self.line)?;
+        write!(f, ", byte {}", self.byte_offset)?;
+        if let Some(column) = self.column {
+            write!(f, ", column {}", column)?;
+            if let Some(name) = &self.column_name {
+                write!(f, " ({:?})", name)?;
+            }
+        }
+        write!(f, ": {:?}", self.snippet)
+    }
+}
+
 #[derive(Debug)]
 pub enum CsvError {
     IO(std::io::Error),
     ParseError(String),
-    InvalidHeader(String),
-    InvalidRow(String),
+    InvalidHeader(Diagnostic),
+    InvalidRow(Diagnostic),
     InvalidColumn(String),
 }
 
@@ -68,16 +101,498 @@ impl From<std::io::Error> for CsvError {
         CsvError::IO(error)
     }
 }
+
+impl std::fmt::Display for CsvError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            CsvError::IO(error) => write!(f, "I/O error: {}", error),
+            CsvError::ParseError(message) => write!(f, "parse error: {}", message),
+            CsvError::InvalidHeader(diagnostic) => write!(f, "invalid header: {}", diagnostic),
+            CsvError::InvalidRow(diagnostic) => write!(f, "invalid row: {}", diagnostic),
+            CsvError::InvalidColumn(message) => write!(f, "invalid column: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for CsvError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            CsvError::IO(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+/* A parse failure inside one record, before it is placed in the input: the byte offset into the
+ * record and the index of the value. */
+struct FieldError {
+    message: &'static str,
+    offset: usize,
+    column: Option<usize>,
+}
+
+/* Where a record starts in the input. */
+#[derive(Clone, Copy)]
+struct RecordStart {
+    record: usize,
+    line: usize,
+    byte_offset: u64,
+}
+
+impl RecordStart {
+    /* Places an error found at `offset` bytes into `text`, which starts here. */
+    fn diagnose(self, text: &str, offset: usize, message: &str) -> Diagnostic {
+        let before: String = text[..offset].chars().rev().take(20).collect();
+        let after: String = text[offset..].chars().take(20).collect();
+        Diagnostic {
+            message: message.to_string(),
+            record: self.record,
+            line: self.line + text[..offset].matches('\n').count(),
+            byte_offset: self.byte_offset + offset as u64,
+            column: None,
+            column_name: None,
+            snippet: before.chars().rev().chain(after.chars()).collect(),
+        }
+    }
+}
 use std::collections::HashMap;
 
 type Row = HashMap<String, String>;
 
 use std::io::BufRead;
 
+/// How `parse_line` splits a row into values.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum QuotingMode {
+    /// RFC 4180: quotes are optional, a doubled quote inside a quoted value stands for one quote,
+    /// and quoted values may contain the delimiter. A quoted value fills its whole field, so
+    /// `a, "b"` is an error: the second value starts with a space and can't contain a quote.
+    #[default]
+    Rfc4180,
+    /// The original format: every value is wrapped in quotes and ends at the next quote.
+    /// Whitespace outside quotes is always trimmed, so `"a", "b"` reads fine, and there is no
+    /// escape character.
+    Strict,
+}
+
+/// Which whitespace the reader drops. Whitespace inside quotes is always kept.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Trim {
+    /// Nothing is trimmed, whitespace next to a delimiter is part of the value.
+    None,
+    /// Only the column names in the header.
+    Headers,
+    /// Only the values, outside quotes.
+    Fields,
+    /// Column names and values, as `Csv::new` always did.
+    #[default]
+    All,
+}
+
+impl Trim {
+    fn headers(self) -> bool {
+        matches!(self, Trim::Headers | Trim::All)
+    }
+
+    fn fields(self) -> bool {
+        matches!(self, Trim::Fields | Trim::All)
+    }
+}
+
+/// The line ending `write_to` uses. Reading accepts both.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LineTerminator {
+    #[default]
+    Lf,
+    CrLf,
+}
+
+impl LineTerminator {
+    fn as_str(self) -> &'static str {
+        match self {
+            LineTerminator::Lf => "\n",
+            LineTerminator::CrLf => "\r\n",
+        }
+    }
+}
+
+/// The format of a CSV input: what separates values, how they are quoted, and so on. The default
+/// is the comma separated format `Csv::new` reads. Fields can be set directly, or chained:
+///
+/// CsvDialect::default().delimiter(';').comment("#")
+/// CsvDialect::tsv().has_header(false)
+///
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CsvDialect {
+    pub delimiter: char,
+    pub quote: char,
+    /// Inside a quoted value, takes the next character literally. A doubled quote works either way.
+    pub escape: Option<char>,
+    /// Lines starting with this prefix are skipped, unless they continue a quoted value.
+    pub comment: Option<String>,
+    pub trim: Trim,
+    /// Without a header, the first line is a row and columns are named by position: "0", "1"...
+    pub has_header: bool,
+    pub quoting: QuotingMode,
+    pub line_terminator: LineTerminator,
+}
+
+impl Default for CsvDialect {
+    fn default() -> Self {
+        Self {
+            delimiter: ',',
+            quote: '"',
+            escape: None,
+            comment: None,
+            trim: Trim::All,
+            has_header: true,
+            quoting: QuotingMode::Rfc4180,
+            line_terminator: LineTerminator::Lf,
+        }
+    }
+}
+
+impl CsvDialect {
+    /// Tab separated values. Whitespace is kept, since spaces in a TSV value are usually data.
+    pub fn tsv() -> Self {
+        Self { delimiter: '\t', trim: Trim::None, ..Self::default() }
+    }
+
+    pub fn delimiter(mut self, delimiter: char) -> Self {
+        self.delimiter = delimiter;
+        self
+    }
+
+    pub fn quote(mut self, quote: char) -> Self {
+        self.quote = quote;
+        self
+    }
+
+    pub fn escape(mut self, escape: char) -> Self {
+        self.escape = Some(escape);
+        self
+    }
+
+    pub fn comment(mut self, prefix: &str) -> Self {
+        self.comment = Some(prefix.to_string());
+        self
+    }
+
+    pub fn trim(mut self, trim: Trim) -> Self {
+        self.trim = trim;
+        self
+    }
+
+    pub fn has_header(mut self, has_header: bool) -> Self {
+        self.has_header = has_header;
+        self
+    }
+
+    pub fn quoting(mut self, quoting: QuotingMode) -> Self {
+        self.quoting = quoting;
+        self
+    }
+
+    pub fn line_terminator(mut self, line_terminator: LineTerminator) -> Self {
+        self.line_terminator = line_terminator;
+        self
+    }
+
+    fn validate(&self) -> Result<(), CsvError> {
+        let special = [self.delimiter, self.quote, '\n', '\r'];
+        for (i, c) in special.iter().enumerate() {
+            if special[i + 1..].contains(c) || Some(*c) == self.escape && *c != self.quote {
+                return Err(CsvError::ParseError(
+                    format!("{:?} has more than one meaning in the dialect", c)
+                ));
+            }
+        }
+        if self.comment.as_deref() == Some("") {
+            return Err(CsvError::ParseError("Empty comment prefix".to_string()));
+        }
+
+        return Ok(());
+    }
+
+    fn trims_fields(&self) -> bool {
+        self.quoting == QuotingMode::Strict || self.trim.fields()
+    }
+
+    fn is_comment(&self, line: &str) -> bool {
+        self.comment.as_ref().is_some_and(|prefix| line.starts_with(prefix.as_str()))
+    }
+
+    /* Feeds `text` through the quoting rules starting from `state`. A quote only opens a value at
+     * the start of a field, or after blanks in the strict format; a stray quote in an unquoted
+     * value is left for the parser to report. */
+    fn scan_quotes(&self, mut state: QuoteScan, text: &str) -> QuoteScan {
+        let skip_blanks = self.quoting == QuotingMode::Strict;
+        for c in text.chars() {
+            state = match state {
+                QuoteScan::FieldStart if c == self.quote => QuoteScan::Quoted,
+                QuoteScan::FieldStart if c == self.delimiter => QuoteScan::FieldStart,
+                QuoteScan::FieldStart if skip_blanks && c.is_whitespace() => QuoteScan::FieldStart,
+                QuoteScan::FieldStart => QuoteScan::Unquoted,
+                QuoteScan::Unquoted if c == self.delimiter => QuoteScan::FieldStart,
+                QuoteScan::Unquoted => QuoteScan::Unquoted,
+                QuoteScan::Quoted if c == self.quote => QuoteScan::QuotedQuote,
+                QuoteScan::Quoted if Some(c) == self.escape => QuoteScan::QuotedEscape,
+                QuoteScan::Quoted | QuoteScan::QuotedEscape => QuoteScan::Quoted,
+                QuoteScan::QuotedQuote if c == self.quote => QuoteScan::Quoted,
+                QuoteScan::QuotedQuote if c == self.delimiter => QuoteScan::FieldStart,
+                QuoteScan::QuotedQuote => QuoteScan::Unquoted,
+            };
+        }
+
+        return state;
+    }
+
+    /* Splits one record into values by the dialect's quoting mode. */
+    fn parse_values(&self, line: &str, trim: bool) -> Result<Vec<String>, FieldError> {
+        match self.quoting {
+            QuotingMode::Rfc4180 => {
+                parse_values_rfc4180(line, self, trim).map(|(values, _)| values)
+            },
+            QuotingMode::Strict => {
+                let parsed = parse_values_strict(line, self.delimiter, self.quote, false);
+                parsed.map(|(values, _)| values)
+            },
+        }
+    }
+
+    /* Splits the header into column names, with the byte offset each one starts at. In RFC 4180 it
+     * is split like a row; the strict format also allows the bare names it always had. */
+    fn parse_header(&self, line: &str) -> Result<(Vec<String>, Vec<usize>), FieldError> {
+        match self.quoting {
+            QuotingMode::Rfc4180 => parse_values_rfc4180(line, self, self.trim.headers()),
+            QuotingMode::Strict => parse_values_strict(line, self.delimiter, self.quote, true),
+        }
+    }
+
+    /* Writes `value` so that reading it back with this dialect gives the same string. Column
+     * names are quoted only when they have to be, values always are. The strict format has no
+     * way to write a quote inside a value, so that is a `CsvError::ParseError`. */
+    fn write_value<W: Write>(&self, writer: &mut W, value: &str, always_quote: bool)
+        -> Result<(), CsvError>
+    {
+        let strict = self.quoting == QuotingMode::Strict;
+        if strict && value.contains(self.quote) {
+            return Err(CsvError::ParseError(
+                format!("{:?} contains a quote, which the strict format can't write", value)
+            ));
+        }
+
+        let needs_quotes = always_quote
+            || value.contains(|c: char| {
+                c == self.delimiter || c == self.quote || c == '\n' || c == '\r'
+            })
+            || value.starts_with(char::is_whitespace)
+            || value.ends_with(char::is_whitespace)
+            || self.is_comment(value);
+        if !needs_quotes {
+            writer.write_all(value.as_bytes())?;
+            return Ok(());
+        }
+
+        let mut quoted = String::with_capacity(value.len() + 2);
+        quoted.push(self.quote);
+        for c in value.chars() {
+            if c == self.quote || Some(c) == self.escape && !strict {
+                quoted.push(self.escape.unwrap_or(self.quote));
+            }
+            quoted.push(c);
+        }
+        quoted.push(self.quote);
+        writer.write_all(quoted.as_bytes())?;
+
+        return Ok(());
+    }
+}
+
+/// What the iterator does with a record it can't read or parse, or whose selection fails.
+/// I/O errors always end the iteration, whatever the policy: the input can't be trusted after one.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ErrorPolicy {
+    /// Yield the error, then stop: every later `next` returns `None`.
+    #[default]
+    FailFast,
+    /// Skip the record and keep the error for `errors`.
+    SkipAndCollect,
+    /// Keep the error for `errors` and yield a row made of whatever values could be parsed, with
+    /// the defaults from `set_defaults` (or empty strings) for the rest. A row whose selection
+    /// fails is skipped, since it parsed fine and there is nothing to replace.
+    ReplaceWithDefaults,
+}
+
 pub struct Csv<R: BufRead> {
     pub columns: Vec<String>,
     reader: R,
     selection: Option<Box<dyn Fn(&Row) -> Result<bool, CsvError>>>,
+    dialect: CsvDialect,
+    line_number: usize,
+    byte_offset: u64,
+    records: usize,
+    /* The first record of an input without a header, read early to count the columns. */
+    pending: Option<(String, RecordStart)>,
+    error_policy: ErrorPolicy,
+    errors: Vec<CsvError>,
+    defaults: Row,
+    finished: bool,
+}
+
+/* Where a record scan stopped, so it can resume on the next physical line. */
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum QuoteScan {
+    FieldStart,
+    Unquoted,
+    Quoted,
+    /* A quote inside a quoted value: either the closing one, or the first half of a doubled one. */
+    QuotedQuote,
+    QuotedEscape,
+}
+
+/* Trims whitespace from both ends of `input`, except a whitespace delimiter like a tab. */
+fn trim_blanks(input: &str, delimiter: char) -> &str {
+    input.trim_matches(|c: char| c.is_whitespace() && c != delimiter)
+}
+
+/* Splits a line where every value must be quoted and no value contains a quote. With `bare`, a
+ * value may also be unquoted and runs up to the next delimiter, like the names in the header of
+ * the original format. Next to the values, returns the byte offset each one starts at. */
+fn parse_values_strict(line: &str, delimiter: char, quote: char, bare: bool)
+    -> Result<(Vec<String>, Vec<usize>), FieldError>
+{
+    /* Every slice below is a suffix of `line`, so its length gives the offset. */
+    let error = |message, rest: &str, column| {
+        Err(FieldError { message, offset: line.len() - rest.len(), column: Some(column) })
+    };
+
+    let mut values = vec![];
+    let mut starts = vec![];
+    let mut copy_of_line = line;
+    loop {
+        let trimmed = copy_of_line
+            .trim_start_matches(|c: char| c.is_whitespace() && c != delimiter);
+        starts.push(line.len() - trimmed.len());
+        let left_quote_rest = skip_next(trimmed, quote);
+        if left_quote_rest == None {
+            if !bare {
+                return error("No first quote", trimmed, values.len());
+            }
+
+            let (value, rest) = take_until(trimmed, delimiter);
+            values.push(trim_blanks(value, delimiter).to_string());
+            match skip_next(rest, delimiter) {
+                Some(other) => copy_of_line = other,
+                None => break,
+            }
+            continue;
+        }
+
+        let value_packed = take_and_skip(left_quote_rest.unwrap(), quote);
+        if value_packed == None {
+            return error("No second quote", trimmed, values.len());
+        }
+        let (value, rest) = value_packed.unwrap();
+        values.push(value.to_string());
+        let rest = rest.trim_start_matches(|c: char| c.is_whitespace() && c != delimiter);
+        let other = skip_next(rest, delimiter);
+        if other == None {
+            if rest.trim_end() != "" {
+                return error("No comma after quote", rest, values.len() - 1);
+            }
+            break;
+        }
+        copy_of_line = other.unwrap();
+    }
+
+    return Ok((values, starts));
+}
+
+/* Splits a line by RFC 4180: a value is either quoted, with a doubled quote (or the escape
+ * character) standing for a literal quote, or runs up to the next delimiter. A quoted value has
+ * to fill the whole field, so nothing may come between a delimiter and an opening quote or
+ * between a closing quote and the next delimiter. Empty values are allowed anywhere, including
+ * after a trailing delimiter. With `trim`, unquoted values lose their surrounding whitespace.
+ * Next to the values, returns the byte offset each one starts at: its opening quote, or its
+ * first character after trimming. */
+fn parse_values_rfc4180(line: &str, dialect: &CsvDialect, trim: bool)
+    -> Result<(Vec<String>, Vec<usize>), FieldError>
+{
+    let line = line.strip_suffix('\n').unwrap_or(line);
+    let line = line.strip_suffix('\r').unwrap_or(line);
+    let is_blank = |&(_, c): &(usize, char)| trim && c.is_whitespace() && c != dialect.delimiter;
+    let is = |next: Option<&(usize, char)>, target: char| next.is_some_and(|&(_, c)| c == target);
+    let unclosed = |offset, column| FieldError { message: "No closing quote", offset, column };
+
+    let mut values = vec![];
+    let mut starts = vec![];
+    let mut chars = line.char_indices().peekable();
+    loop {
+        let column = Some(values.len());
+        let mut value = String::new();
+        if let Some(&(opening, _)) = chars.peek().filter(|&&(_, c)| c == dialect.quote) {
+            starts.push(opening);
+            chars.next();
+            loop {
+                match chars.next() {
+                    None => return Err(unclosed(opening, column)),
+                    Some((_, c)) if c == dialect.quote && is(chars.peek(), c) => {
+                        chars.next();
+                        value.push(c);
+                    },
+                    Some((_, c)) if c == dialect.quote => break,
+                    Some((_, c)) if Some(c) == dialect.escape => match chars.next() {
+                        Some((_, escaped)) => value.push(escaped),
+                        None => return Err(unclosed(opening, column)),
+                    },
+                    Some((_, c)) => value.push(c),
+                }
+            }
+
+            match chars.next() {
+                None => {
+                    values.push(value);
+                    break;
+                },
+                Some((_, c)) if c == dialect.delimiter => values.push(value),
+                Some((offset, _)) => {
+                    return Err(FieldError { message: "No delimiter after quote", offset, column });
+                },
+            }
+        } else {
+            while chars.peek().is_some_and(is_blank) {
+                chars.next();
+            }
+
+            starts.push(chars.peek().map_or(line.len(), |&(offset, _)| offset));
+            let mut last = true;
+            loop {
+                match chars.next() {
+                    None => break,
+                    Some((_, c)) if c == dialect.delimiter => {
+                        last = false;
+                        break;
+                    },
+                    Some((offset, c)) if c == dialect.quote => {
+                        let message = "Quote in unquoted value";
+                        return Err(FieldError { message, offset, column });
+                    },
+                    Some((_, c)) => value.push(c),
+                }
+            }
+
+            if trim {
+                value = trim_blanks(&value, dialect.delimiter).to_string();
+            }
+            values.push(value);
+            if last {
+                break;
+            }
+        }
+    }
+
+    return Ok((values, starts));
 }
 
 use std::io::Write;
@@ -108,32 +623,161 @@ impl<R: BufRead> Csv<R> {
     /// `.trim()`).
     ///
 
-    pub fn new(mut reader: R) -> Result<Self, CsvError> {
-        let mut header_line = String::new();
-        let header_size = reader.read_line(&mut header_line)?;
+    pub fn new(reader: R) -> Result<Self, CsvError> {
+        Self::with_dialect(reader, CsvDialect::default())
+    }
+
+    /// Like `new`, but reads the input in the given dialect: another delimiter or quote, comment
+    /// lines, no header and so on. The header is split like a row, with optional quotes, and its
+    /// names trimmed if the dialect's `trim` says so. A dialect that gives one character two
+    /// meanings, like a `"` delimiter with `"` quotes, is a `CsvError::ParseError`.
+    ///
+    pub fn with_dialect(reader: R, dialect: CsvDialect) -> Result<Self, CsvError> {
+        dialect.validate()?;
+        let mut csv = Self::raw(reader, dialect);
 
-        if header_size == 0 {
-            return Err(CsvError::InvalidHeader("InvalidHeader indeed".to_string()));
+        let (header_line, mut start) = match csv.read_record() {
+            Ok(Some(header)) => header,
+            Ok(None) if !csv.dialect.has_header => return Ok(csv),
+            Ok(None) => {
+                let mut start = csv.next_record_start();
+                start.record = 0;
+                return Err(CsvError::InvalidHeader(start.diagnose("", 0, "No header")));
+            },
+            Err(CsvError::InvalidRow(mut diagnostic)) => {
+                diagnostic.record = 0;
+                return Err(CsvError::InvalidHeader(diagnostic));
+            },
+            Err(error) => return Err(error),
+        };
+
+        if !csv.dialect.has_header {
+            let count = match csv.dialect.parse_values(&header_line, csv.dialect.trims_fields()) {
+                Ok(values) => values.len(),
+                Err(error) => {
+                    return Err(CsvError::InvalidHeader(csv.diagnose(error, &header_line, start)));
+                },
+            };
+            csv.columns = (0..count).map(|index| index.to_string()).collect();
+            csv.pending = Some((header_line, start));
+            return Ok(csv);
         }
 
-        let mut headers = vec![];
-        loop {
-            let (column, rest) = take_until(&header_line, ',');
-            let new_col = column.trim().to_string();
-            if headers.contains(&new_col) {
-                return Err(CsvError::InvalidHeader("InvalidHeader indeed".to_string()));
+        csv.records = 0;
+        start.record = 0;
+        let (headers, starts) = match csv.dialect.parse_header(&header_line) {
+            Ok(parsed) => parsed,
+            Err(error) => {
+                return Err(CsvError::InvalidHeader(csv.diagnose(error, &header_line, start)));
+            },
+        };
+        for (index, column) in headers.iter().enumerate() {
+            if headers[..index].contains(column) {
+                let offset = starts[index];
+                let error = FieldError { message: "Duplicate column", offset, column: Some(index) };
+                let mut diagnostic = csv.diagnose(error, &header_line, start);
+                diagnostic.column_name = Some(column.to_string());
+                return Err(CsvError::InvalidHeader(diagnostic));
             }
+        }
 
-            headers.push(new_col);
+        csv.columns = headers;
+        return Ok(csv);
+    }
 
-            if rest == "" {
-                break;
+    /* A reader at the very start of the input, with no columns yet. */
+    fn raw(reader: R, dialect: CsvDialect) -> Self {
+        Self {
+            columns: vec![],
+            reader,
+            selection: None,
+            dialect,
+            line_number: 0,
+            byte_offset: 0,
+            records: 0,
+            pending: None,
+            error_policy: ErrorPolicy::default(),
+            errors: vec![],
+            defaults: Row::new(),
+            finished: false,
+        }
+    }
+
+    /// The dialect this input is read in.
+    ///
+    pub fn dialect(&self) -> &CsvDialect {
+        &self.dialect
+    }
+
+    /* Where the next record would start, if there is one. */
+    fn next_record_start(&self) -> RecordStart {
+        RecordStart {
+            record: self.records + 1,
+            line: self.line_number + 1,
+            byte_offset: self.byte_offset,
+        }
+    }
+
+    /* Places a parse failure in `record`, which starts at `start`. */
+    fn diagnose(&self, error: FieldError, record: &str, start: RecordStart) -> Diagnostic {
+        let mut diagnostic = start.diagnose(record, error.offset, error.message);
+        diagnostic.column = error.column;
+        diagnostic.column_name = error.column.and_then(|index| self.columns.get(index).cloned());
+        return diagnostic;
+    }
+
+    /* Reads the next record, which spans more than one physical line when a quoted value contains
+     * a line break. `\n` and `\r\n` endings both work; the line breaks inside a value are kept
+     * as they are. Comment lines are skipped. Returns `None` at the end of the input. */
+    fn read_record(&mut self) -> Result<Option<(String, RecordStart)>, CsvError> {
+        if let Some(pending) = self.pending.take() {
+            return Ok(Some(pending));
+        }
+
+        let mut record = String::new();
+        let mut state = QuoteScan::FieldStart;
+        let mut start = self.next_record_start();
+
+        loop {
+            let line_start = record.len();
+            let read = self.reader.read_line(&mut record)?;
+            if read == 0 {
+                if record.is_empty() {
+                    return Ok(None);
+                }
+                self.records += 1;
+
+                /* The parser runs into the same open quote and knows which value it is in. */
+                let error = match self.dialect.parse_values(&record, self.dialect.trims_fields()) {
+                    Err(error) => error,
+                    Ok(_) => FieldError { message: "", offset: 0, column: None },
+                };
+                let mut diagnostic = self.diagnose(error, &record, start);
+                diagnostic.message = format!("Unterminated quote starting on line {}", start.line);
+                return Err(CsvError::InvalidRow(diagnostic));
+            }
+            self.line_number += 1;
+            self.byte_offset += read as u64;
+
+            if line_start == 0 && self.dialect.is_comment(&record) {
+                record.clear();
+                start = self.next_record_start();
+                continue;
             }
 
-            header_line = skip_next(rest, ',').unwrap().to_string();
+            state = self.dialect.scan_quotes(state, &record[line_start..]);
+            if state != QuoteScan::Quoted && state != QuoteScan::QuotedEscape {
+                self.records += 1;
+                return Ok(Some((record, start)));
+            }
         }
+    }
 
-        Ok(Self {columns: headers, reader, selection: None})
+    /// Switches between RFC 4180 parsing (the default) and the strict format, in which every
+    /// value has to be quoted. Only affects rows read after the call.
+    ///
+    pub fn set_quoting(&mut self, quoting: QuotingMode) {
+        self.dialect.quoting = quoting;
     }
 
     /// Функцията приема следващия ред за обработка и конструира `Row` стойност
@@ -162,34 +806,34 @@ impl<R: BufRead> Csv<R> {
     ///
     /// Всички грешки, които ще връщате, се очаква да бъдат `CsvError::InvalidRow`.
     ///
+    /// The steps above describe `QuotingMode::Strict`. With `QuotingMode::Rfc4180`, the default,
+    /// quotes are optional, `""` inside a quoted value is a literal `"` and empty values are
+    /// allowed.
+    ///
+    /// The position in an error assumes `line` is the next record of the input.
+    ///
     pub fn parse_line(&mut self, line: &str) -> Result<Row, CsvError> {
-        let mut values = vec![];
-        let mut copy_of_line = line.clone();
-        loop {
-            let left_quote_rest = skip_next(copy_of_line.trim(), '"');
-            if left_quote_rest == None {
-                return Err(CsvError::InvalidRow("No first quote".to_string()));
-            }
-
-            let value_packed = take_and_skip(left_quote_rest.unwrap(), '"');
-            if value_packed == None {
-                return Err(CsvError::InvalidRow("No second quote".to_string()));
-            }
-            let (value, rest) = value_packed.unwrap();
-            values.push(value);
-            let other = skip_next(rest.trim(), ',');
-            if other == None {
-                if rest.trim() != "" {
-                    return Err(CsvError::InvalidRow("No comma after quote".to_string()));
-                }
-                break;
-            }
-            copy_of_line = other.unwrap();
-        }
+        let start = self.next_record_start();
+        let values = match self.dialect.parse_values(line, self.dialect.trims_fields()) {
+            Ok(values) => values,
+            Err(error) => return Err(CsvError::InvalidRow(self.diagnose(error, line, start))),
+        };
+        return self.row_from_values(&values, line, start);
+    }
 
+    fn row_from_values(&self, values: &[String], record: &str, start: RecordStart)
+        -> Result<Row, CsvError>
+    {
         /* map values to keys */
         if values.len() != self.columns.len() {
-            return Err(CsvError::InvalidRow("Number of values and columns is different".to_string()));
+            let end = record.trim_end_matches(['\n', '\r']).len();
+            let column = values.len().min(self.columns.len());
+            let error = FieldError {
+                message: "Number of values and columns is different",
+                offset: end,
+                column: Some(column),
+            };
+            return Err(CsvError::InvalidRow(self.diagnose(error, record, start)));
         }
 
         let zipped = self.columns.iter().zip(values.iter());
@@ -202,6 +846,72 @@ impl<R: BufRead> Csv<R> {
         return Ok(res);
     }
 
+    /// Chooses what the iterator does with broken records, see `ErrorPolicy`. The default is
+    /// `ErrorPolicy::FailFast`.
+    ///
+    pub fn set_error_policy(&mut self, error_policy: ErrorPolicy) {
+        self.error_policy = error_policy;
+    }
+
+    /// The values `ErrorPolicy::ReplaceWithDefaults` puts in columns a broken record didn't give.
+    /// Columns missing from `defaults` get an empty string.
+    ///
+    pub fn set_defaults(&mut self, defaults: Row) {
+        self.defaults = defaults;
+    }
+
+    /// The errors kept by `ErrorPolicy::SkipAndCollect` and `ErrorPolicy::ReplaceWithDefaults`,
+    /// oldest first. To look at them after a loop, iterate with `.by_ref()`.
+    ///
+    pub fn errors(&self) -> &[CsvError] {
+        &self.errors
+    }
+
+    /// Like `errors`, but hands the errors over and starts a new list.
+    ///
+    pub fn take_errors(&mut self) -> Vec<CsvError> {
+        std::mem::take(&mut self.errors)
+    }
+
+    /* Reads the next record and splits it into values, next to the record itself and where it
+     * starts. Returns `None` at the end of the input. */
+    fn read_values(&mut self) -> Result<Option<(Vec<String>, String, RecordStart)>, CsvError> {
+        let (record, start) = match self.read_record()? {
+            Some(record) => record,
+            None => return Ok(None),
+        };
+
+        return match self.dialect.parse_values(&record, self.dialect.trims_fields()) {
+            Ok(values) => Ok(Some((values, record, start))),
+            Err(error) => Err(CsvError::InvalidRow(self.diagnose(error, &record, start))),
+        };
+    }
+
+    /* Applies the error policy to a record that failed: an error to yield, `None` to skip it, or
+     * a replacement row. */
+    fn recover(&mut self, error: CsvError, values: &[String]) -> Result<Option<Row>, CsvError> {
+        if self.error_policy == ErrorPolicy::FailFast || matches!(error, CsvError::IO(_)) {
+            self.finished = true;
+            return Err(error);
+        }
+
+        self.errors.push(error);
+        if self.error_policy == ErrorPolicy::SkipAndCollect {
+            return Ok(None);
+        }
+
+        let mut row = Row::new();
+        for (index, column) in self.columns.iter().enumerate() {
+            let value = values.get(index)
+                .or_else(|| self.defaults.get(column))
+                .cloned()
+                .unwrap_or_default();
+            row.insert(column.to_string(), value);
+        }
+
+        return Ok(Some(row));
+    }
+
     /// Подадената функция, "callback", се очаква да се запази и да се използва по-късно за
     /// филтриране -- при итерация, само редове, за които се връща `true` се очаква да се извадят.
     ///
@@ -223,27 +933,39 @@ impl<R: BufRead> Csv<R> {
     /// В зависимост от това как си имплементирате метода, `mut` може би няма да ви трябва за
     /// `self` -- ако имате warning-и, просто го махнете.
     ///
+    /// The output is in the same dialect as the input, so it reads back the same way. Values are
+    /// always quoted. In the strict format, values are separated by ", " as before; in RFC 4180,
+    /// by the bare delimiter. Lines end in the dialect's terminator. Without a header, none is
+    /// written.
+    ///
     pub fn write_to<W: Write>(mut self, mut writer: W) -> Result<(), CsvError> {
         let length_of_columns = self.columns.len();
+        let mut separator = self.dialect.delimiter.to_string();
+        if self.dialect.quoting == QuotingMode::Strict {
+            separator.push(' ');
+        }
+        let terminator = self.dialect.line_terminator.as_str().as_bytes();
 
-        for (index, column_header) in self.columns.iter().enumerate() {
-            writer.write(column_header.as_bytes())?;
-            if index != length_of_columns - 1 {
-                writer.write(b", ")?;
+        if self.dialect.has_header {
+            for (index, column_header) in self.columns.iter().enumerate() {
+                self.dialect.write_value(&mut writer, column_header, false)?;
+                if index != length_of_columns - 1 {
+                    writer.write_all(separator.as_bytes())?;
+                }
             }
+            writer.write_all(terminator)?;
         }
-        writer.write(b"\n")?;
 
         while let Some(row) = self.next() {
+            let row = row?;
             for (index, column_header) in self.columns.iter().enumerate() {
-                writer.write(b"\"")?;
-                writer.write(row.as_ref().unwrap().get(column_header).unwrap().as_bytes())?;
-                writer.write(b"\"")?;
+                let value = &row[column_header];
+                self.dialect.write_value(&mut writer, value, true)?;
                 if index != length_of_columns - 1 {
-                    writer.write(b", ")?;
+                    writer.write_all(separator.as_bytes())?;
                 }
             }
-            writer.write(b"\n")?;
+            writer.write_all(terminator)?;
         }
 
         writer.flush()?;
@@ -273,23 +995,645 @@ impl<R: BufRead> Iterator for Csv<R> {
     /// Да, тази функция връща `Option<Result<...>>` :). `Option` защото може да има, може да няма
     /// следващ ред, `Result` защото четенето на реда (от примерно файл) може да не сработи.
     ///
+    /// A "line" in step 1 is really a record: while a quoted value is open, reading continues on
+    /// the next physical line. Input that ends inside quotes is a `CsvError::InvalidRow` naming
+    /// the line the record started on.
+    ///
+    /// Once an error is returned, the iteration is over. `set_error_policy` can make it skip or
+    /// replace broken records instead; I/O errors end it regardless.
+    ///
     fn next(&mut self) -> Option<Self::Item> {
-        loop {
-            let mut next_line = String::new();
-            let len_of_next_line = self.reader.read_line(&mut next_line).unwrap();
+        while !self.finished {
+            /* On failure, keep the values that did parse, for `ErrorPolicy::ReplaceWithDefaults`. */
+            let parsed_row = match self.read_values() {
+                Ok(Some((values, record, start))) => {
+                    self.row_from_values(&values, &record, start).map_err(|error| (error, values))
+                },
+                Ok(None) => return None,
+                Err(error) => Err((error, vec![])),
+            };
+            let parsed_row = match parsed_row {
+                Ok(row) => row,
+                Err((error, values)) => match self.recover(error, &values) {
+                    Ok(Some(row)) => row,
+                    Ok(None) => continue,
+                    Err(error) => return Some(Err(error)),
+                },
+            };
 
-            if len_of_next_line == 0 {
-                return None;
+            let selected = match &self.selection {
+                Some(selection) => selection(&parsed_row),
+                None => Ok(true),
+            };
+            match selected {
+                Ok(true) => return Some(Ok(parsed_row)),
+                Ok(false) => (),
+                Err(error) if self.error_policy == ErrorPolicy::FailFast => {
+                    self.finished = true;
+                    return Some(Err(error));
+                },
+                Err(error) => self.errors.push(error),
             }
+        }
+
+        return None;
+    }
+}
 
-            let parsed_row = self.parse_line(&next_line).unwrap();
+use std::io::{Chain, Cursor, Read};
 
-            if self.selection.is_none()
-                || (self.selection.as_ref().unwrap())(&parsed_row).is_ok()
-                && (self.selection.as_ref().unwrap())(&parsed_row).unwrap() {
-                return Some(Ok(parsed_row));
+const SNIFF_DELIMITERS: [char; 5] = [',', ';', '\t', '|', ':'];
+const SNIFF_QUOTES: [char; 2] = ['"', '\''];
+
+/// What `sniff` guessed about an input.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Sniffed {
+    pub dialect: CsvDialect,
+    /// From 0 to 1. The share of sampled records with the guessed number of columns, lowered for
+    /// samples of fewer than five records, for a single column, and when nothing in the sample
+    /// hinted at whether there is a header.
+    pub confidence: f64,
+}
+
+/// The input `sniff` was given, with the lines it read put back in front.
+pub type SniffReplay<R> = Chain<Cursor<Vec<u8>>, R>;
+
+/* A delimiter being tried: the dialect, the records of the most common width, and their share. */
+type SniffCandidate = (CsvDialect, Vec<Vec<String>>, f64);
+
+/* Splits `sample` into records with `dialect`, `None` for the ones that don't parse. A record cut
+ * off by the end of the sample is dropped. */
+fn sample_rows(sample: &str, dialect: &CsvDialect) -> Vec<Option<Vec<String>>> {
+    let mut csv = Csv::raw(sample.as_bytes(), dialect.clone());
+
+    let mut rows = vec![];
+    while let Ok(Some((record, _))) = csv.read_record() {
+        rows.push(dialect.parse_values(&record, dialect.trims_fields()).ok());
+    }
+
+    return rows;
+}
+
+/* The quote character that most often opens a value: right at the start of a line or after a
+ * possible delimiter, give or take spaces. */
+fn guess_quote(sample: &str) -> char {
+    let mut best = ('"', 0);
+    for quote in SNIFF_QUOTES {
+        let mut count = 0;
+        for line in sample.lines() {
+            let mut previous = None;
+            for c in line.chars() {
+                if c == quote && previous.is_none_or(|p| SNIFF_DELIMITERS.contains(&p)) {
+                    count += 1;
+                }
+                if c != ' ' {
+                    previous = Some(c);
+                }
             }
         }
+        if count > best.1 {
+            best = (quote, count);
+        }
     }
+
+    return best.0;
 }
 
+fn is_numeric(value: &str) -> bool {
+    value.trim().parse::<f64>().is_ok()
+}
+
+/* Whether the first row looks like column names, and whether anything in the sample said so
+ * either way. A column votes for a header when its values are all numbers but the first isn't,
+ * or all have one length but the first has another, and against when the first row fits in. */
+fn guess_header(rows: &[Vec<String>]) -> (bool, bool) {
+    let first = &rows[0];
+    for (index, name) in first.iter().enumerate() {
+        if name.is_empty() || first[..index].contains(name) {
+            return (false, true);
+        }
+    }
+
+    let mut votes = 0;
+    for column in 0..first.len() {
+        let values: Vec<&str> = rows[1..].iter().map(|row| row[column].as_str()).collect();
+        if values.is_empty() {
+            break;
+        }
+
+        if values.iter().all(|value| is_numeric(value)) {
+            votes += if is_numeric(&first[column]) { -1 } else { 1 };
+        } else if values.iter().all(|value| value.len() == values[0].len()) {
+            votes += if first[column].len() == values[0].len() { -1 } else { 1 };
+        }
+    }
+
+    if votes != 0 {
+        return (votes > 0, true);
+    }
+    return (!first.iter().any(|name| is_numeric(name)), false);
+}
+
+/// Reads up to `max_lines` lines of `reader` and guesses the dialect they are in: the delimiter
+/// out of `,`, `;`, tab, `|` and `:`, the quote out of `"` and `'`, whether the first line is a
+/// header and which line ending is used. Everything else is left at the default; a tab separated
+/// input also keeps its whitespace, like `CsvDialect::tsv`.
+///
+/// The lines read are not lost: the second result replays them before the rest of `reader`, so
+/// it can go straight to `Csv::with_dialect`. An empty input gives the default dialect with a
+/// confidence of 0.
+///
+pub fn sniff<R: BufRead>(mut reader: R, max_lines: usize)
+    -> Result<(Sniffed, SniffReplay<R>), CsvError>
+{
+    let mut sample = String::new();
+    let (mut lf, mut crlf) = (0, 0);
+    for _ in 0..max_lines {
+        let line_start = sample.len();
+        if reader.read_line(&mut sample)? == 0 {
+            break;
+        }
+        if sample[line_start..].ends_with("\r\n") {
+            crlf += 1;
+        } else if sample[line_start..].ends_with('\n') {
+            lf += 1;
+        }
+    }
+
+    let mut dialect = CsvDialect::default().quote(guess_quote(&sample));
+    if crlf > lf {
+        dialect.line_terminator = LineTerminator::CrLf;
+    }
+
+    /* Pick the delimiter that splits the most records into the same number of values, preferring
+     * more than one value, then more values. */
+    let mut best: Option<SniffCandidate> = None;
+    for delimiter in SNIFF_DELIMITERS {
+        let mut candidate = dialect.clone().delimiter(delimiter).has_header(false);
+        if delimiter == '\t' {
+            candidate.trim = Trim::None;
+        }
+
+        let rows = sample_rows(&sample, &candidate);
+        let mut widths: HashMap<usize, usize> = HashMap::new();
+        for row in rows.iter().flatten() {
+            *widths.entry(row.len()).or_insert(0) += 1;
+        }
+        let most_common = widths.iter().max_by_key(|&(width, count)| (count, width));
+        let Some((&width, &count)) = most_common else {
+            continue;
+        };
+
+        let consistency = count as f64 / rows.len() as f64;
+        let better = match &best {
+            None => true,
+            Some((_, best_rows, best_consistency)) => {
+                let best_width = best_rows[0].len();
+                (width > 1, consistency, width) > (best_width > 1, *best_consistency, best_width)
+            },
+        };
+        if better {
+            let rows = rows.into_iter().flatten().filter(|row| row.len() == width).collect();
+            best = Some((candidate, rows, consistency));
+        }
+    }
+
+    let replay = Cursor::new(sample.into_bytes()).chain(reader);
+    let Some((mut dialect, rows, mut confidence)) = best else {
+        return Ok((Sniffed { dialect, confidence: 0.0 }, replay));
+    };
+
+    let (has_header, evidence) = guess_header(&rows);
+    dialect.has_header = has_header;
+    confidence *= rows.len().min(5) as f64 / 5.0;
+    if rows[0].len() == 1 {
+        confidence *= 0.5;
+    }
+    if !evidence {
+        confidence *= 0.8;
+    }
+
+    return Ok((Sniffed { dialect, confidence }, replay));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn header_error(input: &str, dialect: CsvDialect) -> Diagnostic {
+        match Csv::with_dialect(input.as_bytes(), dialect) {
+            Err(CsvError::InvalidHeader(diagnostic)) => diagnostic,
+            Err(error) => panic!("{}", error),
+            Ok(_) => panic!("{:?} has a valid header", input),
+        }
+    }
+
+    #[test]
+    fn duplicate_columns_point_at_the_second_one() {
+        let diagnostic = header_error("ab,a,a\n", CsvDialect::default());
+        assert_eq!((diagnostic.byte_offset, diagnostic.column), (5, Some(2)));
+        assert_eq!(diagnostic.column_name.as_deref(), Some("a"));
+
+        let diagnostic = header_error("\"x\"\"y\",\"x\"\"y\"\n", CsvDialect::default());
+        assert_eq!((diagnostic.byte_offset, diagnostic.column), (7, Some(1)));
+        assert_eq!(diagnostic.column_name.as_deref(), Some("x\"y"));
+    }
+
+    #[test]
+    fn a_missing_header_is_record_zero() {
+        let diagnostic = header_error("", CsvDialect::default());
+        assert_eq!((diagnostic.record, diagnostic.line, diagnostic.byte_offset), (0, 1, 0));
+
+        let diagnostic = header_error("# only a comment\n", CsvDialect::default().comment("#"));
+        assert_eq!((diagnostic.record, diagnostic.line, diagnostic.byte_offset), (0, 2, 17));
+    }
+
+    fn values(line: &str, dialect: &CsvDialect) -> Option<Vec<String>> {
+        dialect.parse_values(line, dialect.trims_fields()).ok()
+    }
+
+    fn strings(values: &[&str]) -> Option<Vec<String>> {
+        Some(values.iter().map(|value| value.to_string()).collect())
+    }
+
+    #[test]
+    fn rfc4180_values() {
+        let dialect = CsvDialect::default();
+        let cases = [
+            ("1,foo", strings(&["1", "foo"])),
+            ("\"a\"\"b\",\"\"\"\"", strings(&["a\"b", "\""])),
+            ("\"a,b\",c", strings(&["a,b", "c"])),
+            (",,", strings(&["", "", ""])),
+            ("\"\",", strings(&["", ""])),
+            ("a,", strings(&["a", ""])),
+            ("a,\"\"\r\n", strings(&["a", ""])),
+            ("a,b\r\n", strings(&["a", "b"])),
+            ("  a  , b ", strings(&["a", "b"])),
+            ("\"x\"\r\n", strings(&["x"])),
+            ("\"unclosed", None),
+            ("a,\"unclosed\"\"", None),
+            ("a\"b,c", None),
+            ("a, \"b\"", None),
+            ("\"a\" ,b", None),
+            ("\"a\"b,c", None),
+        ];
+
+        for (line, expected) in cases {
+            assert_eq!(values(line, &dialect), expected, "{:?}", line);
+        }
+
+        let untrimmed = CsvDialect::default().trim(Trim::None);
+        assert_eq!(values(" a , b\r\n", &untrimmed), strings(&[" a ", " b"]));
+    }
+
+    #[test]
+    fn strict_values_need_quotes_and_allow_spaces_around_them() {
+        let dialect = CsvDialect::default().quoting(QuotingMode::Strict);
+        assert_eq!(values("\"a\", \"b, c\"  \n", &dialect), strings(&["a", "b, c"]));
+        assert_eq!(values("  \"\" ,\"x\"", &dialect), strings(&["", "x"]));
+        assert_eq!(values("a, \"b\"", &dialect), None);
+        assert_eq!(values("\"a\", \"b", &dialect), None);
+        assert_eq!(values("\"a\" \"b\"", &dialect), None);
+    }
+
+    #[test]
+    fn a_space_before_an_opening_quote_is_reported_at_the_quote() {
+        let mut csv = Csv::new("name,age\n".as_bytes()).unwrap();
+        match csv.parse_line("x, \"20\"") {
+            Err(CsvError::InvalidRow(diagnostic)) => {
+                assert_eq!(diagnostic.message, "Quote in unquoted value");
+                assert_eq!((diagnostic.byte_offset, diagnostic.column), (12, Some(1)));
+                assert_eq!(diagnostic.column_name.as_deref(), Some("age"));
+            },
+            other => panic!("{:?}", other),
+        }
+
+        csv.set_quoting(QuotingMode::Strict);
+        let row = csv.parse_line("\"x\", \"20\"").unwrap();
+        assert_eq!((row["name"].as_str(), row["age"].as_str()), ("x", "20"));
+    }
+
+    #[test]
+    fn quoted_values_span_lines() {
+        let input = "id,text\n1,\"first\nsecond\"\n2,\"a\r\nb\"\r\n3,\"\"\"\n,\"\"\"\n4,plain\n";
+        let rows: Vec<Row> = Csv::new(input.as_bytes()).unwrap().map(Result::unwrap).collect();
+        let texts: Vec<(&str, &str)> = rows.iter()
+            .map(|row| (row["id"].as_str(), row["text"].as_str()))
+            .collect();
+
+        let expected = [("1", "first\nsecond"), ("2", "a\r\nb"), ("3", "\"\n,\""), ("4", "plain")];
+        assert_eq!(texts, expected);
+    }
+
+    #[test]
+    fn a_quote_left_open_at_the_end_is_an_error_from_its_first_line() {
+        let input = "id,text\n1,ok\r\n2,\"never\r\nclosed\nat all";
+        let mut csv = Csv::new(input.as_bytes()).unwrap();
+        assert_eq!(csv.next().unwrap().unwrap()["text"], "ok");
+
+        match csv.next() {
+            Some(Err(CsvError::InvalidRow(diagnostic))) => {
+                assert_eq!(diagnostic.message, "Unterminated quote starting on line 3");
+                let position = (diagnostic.record, diagnostic.line, diagnostic.byte_offset);
+                assert_eq!(position, (2, 3, 16));
+                assert_eq!(diagnostic.column_name.as_deref(), Some("text"));
+            },
+            other => panic!("{:?}", other),
+        }
+        assert!(csv.next().is_none());
+    }
+
+    fn read_all(input: &str, dialect: CsvDialect) -> (Vec<String>, Vec<Row>) {
+        let csv = Csv::with_dialect(input.as_bytes(), dialect).unwrap();
+        let columns = csv.columns.clone();
+        return (columns, csv.map(Result::unwrap).collect());
+    }
+
+    fn write_all(input: &str, dialect: CsvDialect) -> Result<String, CsvError> {
+        let mut output = vec![];
+        Csv::with_dialect(input.as_bytes(), dialect)?.write_to(&mut output)?;
+        return Ok(String::from_utf8(output).unwrap());
+    }
+
+    #[test]
+    fn dialects_read_their_delimiter_quote_escape_and_comments() {
+        let dialect = CsvDialect::default().delimiter(';').quote('\'').escape('\\').comment("#");
+        let input = "# exported\nname; note\n'Smith; J.';'it\\'s ''here'''\n# skipped\nx;\n";
+        let (columns, rows) = read_all(input, dialect);
+        assert_eq!(columns, ["name", "note"]);
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0]["name"], "Smith; J.");
+        assert_eq!(rows[0]["note"], "it's 'here'");
+        assert_eq!((rows[1]["name"].as_str(), rows[1]["note"].as_str()), ("x", ""));
+
+        let (columns, rows) = read_all(" a \t b\n 1 \t\"2\t3\"\n", CsvDialect::tsv());
+        assert_eq!(columns, [" a ", " b"]);
+        assert_eq!((rows[0][" a "].as_str(), rows[0][" b"].as_str()), (" 1 ", "2\t3"));
+
+        let (columns, rows) = read_all("0,1\n", CsvDialect::default().has_header(false));
+        assert_eq!(columns, ["0", "1"]);
+        assert_eq!((rows[0]["0"].as_str(), rows[0]["1"].as_str()), ("0", "1"));
+
+        let ambiguous = CsvDialect::default().delimiter('"');
+        let res = Csv::with_dialect("a\n".as_bytes(), ambiguous);
+        assert!(matches!(res, Err(CsvError::ParseError(_))));
+    }
+
+    #[test]
+    fn the_strict_header_has_bare_or_quoted_names() {
+        let dialect = CsvDialect::default().quoting(QuotingMode::Strict);
+        let input = "name, \"birth, date\"\n\"x\", \"1 Jan\"\n";
+        let (columns, rows) = read_all(input, dialect.clone());
+        assert_eq!(columns, ["name", "birth, date"]);
+        assert_eq!(rows[0]["birth, date"], "1 Jan");
+
+        let res = Csv::with_dialect("a, \"b\n".as_bytes(), dialect);
+        assert!(matches!(res, Err(CsvError::InvalidHeader(_))));
+    }
+
+    #[test]
+    fn written_output_reads_back_the_same() {
+        let cases = [
+            (
+                "name,note\n\"a,b\",\"say \"\"hi\"\"\"\n\" lead\",\"two\nlines\"\nplain,\n",
+                CsvDialect::default(),
+            ),
+            (
+                "name , note\r\n a ,\"b\r\n\"\r\n",
+                CsvDialect::default().trim(Trim::None).line_terminator(LineTerminator::CrLf),
+            ),
+            (" a \t\"b\tc\"\n 1 \t\" \"\n", CsvDialect::tsv()),
+            (
+                "# a comment\n'#name';note\n'#1';'it\\'s, \\\\ '''\n",
+                CsvDialect::default().delimiter(';').quote('\'').escape('\\').comment("#"),
+            ),
+            ("0,1\n\"\",\"\"\"\"\n", CsvDialect::default().has_header(false)),
+            ("n, m\n\"1\", \" 2 \"\n", CsvDialect::default().quoting(QuotingMode::Strict)),
+        ];
+
+        for (input, dialect) in cases {
+            let read = read_all(input, dialect.clone());
+            let written = write_all(input, dialect.clone()).unwrap();
+            assert_eq!(read_all(&written, dialect.clone()), read, "{:?}", written);
+            assert!(written.ends_with(dialect.line_terminator.as_str()));
+        }
+
+        let strict = CsvDialect::default().quoting(QuotingMode::Strict);
+        let written = write_all("n, m\n\"1\", \" 2 \"\n", strict).unwrap();
+        assert_eq!(written, "n, m\n\"1\", \" 2 \"\n");
+        let written = write_all("n,m\n1,\" 2 \"\n", CsvDialect::default()).unwrap();
+        assert_eq!(written, "n,m\n\"1\",\" 2 \"\n");
+    }
+
+    #[test]
+    fn the_strict_format_refuses_to_write_a_quote() {
+        let strict = CsvDialect::default().quoting(QuotingMode::Strict);
+        let res = strict.write_value(&mut vec![], "say \"hi\"", true);
+        assert!(matches!(res, Err(CsvError::ParseError(_))));
+        assert!(strict.write_value(&mut vec![], "no quotes", true).is_ok());
+
+        /* A bare name in the header is the one place the strict format reads a quote. */
+        let res = write_all("a\"b\n\"x\"\n", strict);
+        assert!(matches!(res, Err(CsvError::ParseError(_))));
+    }
+
+    fn sniff_str(input: &str, max_lines: usize) -> (Sniffed, String) {
+        let (sniffed, mut replay) = sniff(input.as_bytes(), max_lines).unwrap();
+        let mut replayed = String::new();
+        replay.read_to_string(&mut replayed).unwrap();
+        return (sniffed, replayed);
+    }
+
+    #[test]
+    fn sniff_guesses_the_delimiter_quote_and_terminator() {
+        let input = "id;name;price\r\n1;'Smith; J.';2.50\r\n2;Jones;3\r\n3;'O''Neil';4\r\n\
+                     4;x;5\r\n5;y;6\r\n";
+        let (sniffed, replayed) = sniff_str(input, 10);
+        assert_eq!(replayed, input);
+        assert_eq!(sniffed.dialect.delimiter, ';');
+        assert_eq!(sniffed.dialect.quote, '\'');
+        assert_eq!(sniffed.dialect.line_terminator, LineTerminator::CrLf);
+        assert!(sniffed.dialect.has_header);
+        assert_eq!(sniffed.confidence, 1.0);
+
+        let (sniffed, _) = sniff_str("a\tb c\n1\t2\n3\t 4\n", 10);
+        assert_eq!((sniffed.dialect.delimiter, sniffed.dialect.trim), ('\t', Trim::None));
+        assert_eq!(sniffed.dialect.line_terminator, LineTerminator::Lf);
+
+        let (sniffed, _) = sniff_str("a|b|c\n1|2,5|3\n4|5,5|6\n", 10);
+        assert_eq!(sniffed.dialect.delimiter, '|');
+    }
+
+    #[test]
+    fn sniff_guesses_whether_there_is_a_header() {
+        let with_header = [
+            "name,age\nann,31\nbob,42\n",
+            "code,city\nAB1,Sofia\nCD2,Varna\n",
+        ];
+        for input in with_header {
+            assert!(sniff_str(input, 10).0.dialect.has_header, "{:?}", input);
+        }
+
+        let without_header = [
+            "1,2\n3,4\n5,6\n",
+            "ann,31\nbob,42\n",
+            "a,a\nb,c\n",
+            "x,,y\n1,2,3\n",
+        ];
+        for input in without_header {
+            assert!(!sniff_str(input, 10).0.dialect.has_header, "{:?}", input);
+        }
+    }
+
+    #[test]
+    fn sniff_confidence_drops_with_less_evidence() {
+        let (sniffed, replayed) = sniff_str("", 10);
+        assert_eq!((sniffed.dialect, sniffed.confidence), (CsvDialect::default(), 0.0));
+        assert_eq!(replayed, "");
+
+        let consistent = "a,b\n1,2\n3,4\n5,6\n7,8\n9,10\n";
+        let ragged = "a,b\n1,2\n3,4,5\n5,6\n7\n9,10\n";
+        let short = "a,b\n1,2\n";
+        let single_column = "a\n1\n2\n3\n4\n5\n";
+
+        let confidence = |input| sniff_str(input, 10).0.confidence;
+        assert_eq!(confidence(consistent), 1.0);
+        assert!(confidence(ragged) < confidence(consistent));
+        assert!(confidence(short) < confidence(consistent));
+        assert!(confidence(single_column) <= 0.5);
+        for input in [consistent, ragged, short, single_column] {
+            assert!((0.0..=1.0).contains(&confidence(input)), "{:?}", input);
+        }
+    }
+
+    #[test]
+    fn sniff_replays_everything_it_read() {
+        let input = "a,b\n1,\"two\nlines\"\n3,4\n5,6\n";
+        for max_lines in [0, 1, 2, 3, 100] {
+            let (sniffed, replayed) = sniff_str(input, max_lines);
+            assert_eq!(replayed, input, "{}", max_lines);
+
+            if max_lines >= 2 {
+                let (sniffed, replay) = sniff(input.as_bytes(), max_lines).unwrap();
+                let rows: Vec<Row> = Csv::with_dialect(replay, sniffed.dialect).unwrap()
+                    .map(Result::unwrap)
+                    .collect();
+                assert_eq!(rows.len(), 3);
+                assert_eq!(rows[0]["b"], "two\nlines");
+            }
+            assert_eq!(sniffed.dialect.delimiter, ',');
+        }
+    }
+
+    const BROKEN_ROWS: &str = "id,name\n1,ann\n2\n3,\"x\"y\n4,dan\n";
+
+    fn invalid_row_records(errors: &[CsvError]) -> Vec<usize> {
+        errors.iter()
+            .map(|error| match error {
+                CsvError::InvalidRow(diagnostic) => diagnostic.record,
+                other => panic!("{:?}", other),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn fail_fast_stops_after_the_first_error() {
+        let mut csv = Csv::new(BROKEN_ROWS.as_bytes()).unwrap();
+        assert_eq!(csv.next().unwrap().unwrap()["name"], "ann");
+        assert_eq!(invalid_row_records(&[csv.next().unwrap().unwrap_err()]), [2]);
+        assert!(csv.next().is_none());
+        assert!(csv.next().is_none());
+        assert!(csv.errors().is_empty());
+
+        let mut csv = Csv::new("id\n1\n2\n".as_bytes()).unwrap();
+        csv.apply_selection(|row| match row["id"].as_str() {
+            "1" => Err(CsvError::InvalidColumn("no".to_string())),
+            _ => Ok(true),
+        });
+        assert!(matches!(csv.next(), Some(Err(CsvError::InvalidColumn(_)))));
+        assert!(csv.next().is_none());
+    }
+
+    #[test]
+    fn skip_and_collect_keeps_every_diagnostic() {
+        let mut csv = Csv::new(BROKEN_ROWS.as_bytes()).unwrap();
+        csv.set_error_policy(ErrorPolicy::SkipAndCollect);
+        let names: Vec<String> = csv.by_ref().map(|row| row.unwrap()["name"].clone()).collect();
+
+        assert_eq!(names, ["ann", "dan"]);
+        assert_eq!(invalid_row_records(csv.errors()), [2, 3]);
+        match &csv.errors()[1] {
+            CsvError::InvalidRow(diagnostic) => {
+                assert_eq!(diagnostic.message, "No delimiter after quote");
+                assert_eq!((diagnostic.line, diagnostic.byte_offset), (4, 21));
+                assert_eq!(diagnostic.column_name.as_deref(), Some("name"));
+            },
+            other => panic!("{:?}", other),
+        }
+
+        assert_eq!(invalid_row_records(&csv.take_errors()), [2, 3]);
+        assert!(csv.errors().is_empty());
+        assert!(csv.take_errors().is_empty());
+    }
+
+    #[test]
+    fn replace_with_defaults_fills_in_the_missing_values() {
+        let mut csv = Csv::new(BROKEN_ROWS.as_bytes()).unwrap();
+        csv.set_error_policy(ErrorPolicy::ReplaceWithDefaults);
+        csv.set_defaults(Row::from([("name".to_string(), "?".to_string())]));
+        let rows: Vec<(String, String)> = csv.by_ref()
+            .map(|row| row.unwrap())
+            .map(|row| (row["id"].clone(), row["name"].clone()))
+            .collect();
+
+        let expected = [("1", "ann"), ("2", "?"), ("", "?"), ("4", "dan")];
+        let expected: Vec<(String, String)> = expected.iter()
+            .map(|&(id, name)| (id.to_string(), name.to_string()))
+            .collect();
+        assert_eq!(rows, expected);
+        assert_eq!(invalid_row_records(csv.errors()), [2, 3]);
+
+        /* A row whose selection fails parsed fine, so it is skipped rather than replaced. */
+        let mut csv = Csv::new("id\n1\n2\n".as_bytes()).unwrap();
+        csv.set_error_policy(ErrorPolicy::ReplaceWithDefaults);
+        csv.apply_selection(|row| match row["id"].as_str() {
+            "1" => Err(CsvError::InvalidColumn("no".to_string())),
+            _ => Ok(true),
+        });
+        let ids: Vec<String> = csv.by_ref().map(|row| row.unwrap()["id"].clone()).collect();
+        assert_eq!(ids, ["2"]);
+        assert!(matches!(csv.errors(), [CsvError::InvalidColumn(_)]));
+    }
+
+    /* Gives `data`, then fails every read. */
+    struct Broken<'a>(&'a [u8]);
+
+    impl Read for Broken<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            if self.0.is_empty() {
+                return Err(std::io::Error::other("disk on fire"));
+            }
+            let read = self.0.read(buf)?;
+            return Ok(read);
+        }
+    }
+
+    #[test]
+    fn io_errors_end_the_iteration_under_every_policy() {
+        let policies = [
+            ErrorPolicy::FailFast,
+            ErrorPolicy::SkipAndCollect,
+            ErrorPolicy::ReplaceWithDefaults,
+        ];
+        for policy in policies {
+            let reader = std::io::BufReader::new(Broken(b"id\n1\n"));
+            let mut csv = Csv::new(reader).unwrap();
+            csv.set_error_policy(policy);
+
+            assert_eq!(csv.next().unwrap().unwrap()["id"], "1");
+            assert!(matches!(csv.next(), Some(Err(CsvError::IO(_)))), "{:?}", policy);
+            assert!(csv.next().is_none());
+            assert!(csv.errors().is_empty());
+        }
+    }
+}